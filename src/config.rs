@@ -0,0 +1,384 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use ratatui::style::Color;
+use serde::Deserialize;
+
+use crate::editor;
+use crate::theme::Theme;
+
+/// Keys bound to actions on the start screen, unusable as open keys.
+const RESERVED_KEYS: &[char] = &[
+    'q', '/', 'j', 'k', 'g', 'G', 'b', 'd', 'r', 'J', 'K', 'p', 'P',
+];
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Number of recents shown on the start screen.
+    pub max_recents: usize,
+    /// Number of recents under the current project shown above the recents,
+    /// 0 hides the section.
+    pub max_project_recents: usize,
+    /// Number of recently used git repositories shown, 0 hides the section.
+    pub max_projects: usize,
+    /// Number of recents remembered, ranked to pick the `max_recents` shown.
+    pub history_size: usize,
+    pub sort: Sort,
+    /// Unlimited when unset.
+    pub max_bookmarks: Option<usize>,
+    /// Records paths with symlinks resolved, so links to the same file share
+    /// one entry.
+    pub resolve_symlinks: bool,
+    /// Command opening a path, `$HX`, `hx`, `helix` or `$EDITOR` when unset.
+    /// `{path}`, `{line}` and `{col}` are substituted in its arguments.
+    pub editor: Option<String>,
+    /// Characters jump labels are made of, assigned to recents first, then
+    /// bookmarks.
+    pub keys: String,
+    pub header: HeaderConfig,
+    pub paths: PathsConfig,
+    /// Shows a preview of the selected entry on wide terminals, toggled with
+    /// `p`.
+    pub preview: bool,
+    /// `auto` follows the `theme` of Helix' own config, `none` keeps the
+    /// built-in palette, anything else names a Helix theme.
+    pub theme: String,
+    /// Overrides of single colors, taking precedence over the theme.
+    #[serde(rename = "colors")]
+    color_overrides: BTreeMap<String, String>,
+    #[serde(skip)]
+    pub colors: Colors,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_recents: 10,
+            max_project_recents: 5,
+            max_projects: 5,
+            history_size: 100,
+            sort: Sort::Frecency,
+            max_bookmarks: None,
+            resolve_symlinks: false,
+            editor: None,
+            keys: "0123456789acefhi".to_owned(),
+            header: HeaderConfig::default(),
+            paths: PathsConfig::default(),
+            preview: false,
+            theme: "auto".to_owned(),
+            color_overrides: BTreeMap::new(),
+            colors: Colors::default(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeaderConfig {
+    /// A built-in logo (`helix`, `small` or `none`) or the path of a file.
+    pub logo: String,
+    /// Lines shown below the logo.
+    pub extras: Vec<Extra>,
+}
+
+impl Default for HeaderConfig {
+    fn default() -> Self {
+        Self {
+            logo: "helix".to_owned(),
+            extras: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    /// Shows `$HOME` as `~`.
+    pub tilde: bool,
+    pub parents: Parents,
+}
+
+impl Default for PathsConfig {
+    fn default() -> Self {
+        Self {
+            tilde: true,
+            parents: Parents::Full,
+        }
+    }
+}
+
+/// How the parent directories of listed paths are shown.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Parents {
+    Full,
+    /// All but the last directory cut to their first char, like fish does.
+    Short,
+    /// Only as many directories as tell entries with the same name apart.
+    Unique,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Extra {
+    /// The current date.
+    Date,
+    /// A random built-in quote.
+    Quote,
+    /// The output of `fortune -s`, or a quote if it is not installed.
+    Fortune,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sort {
+    /// Most recently opened first.
+    Mru,
+    /// Open count weighted by how recently the item was opened.
+    Frecency,
+    /// Most often opened first.
+    Frequency,
+}
+
+#[derive(Clone, Copy)]
+pub struct Colors {
+    pub logo: Color,
+    pub header: Color,
+    pub bracket: Color,
+    pub key: Color,
+    pub path: Color,
+    pub name: Color,
+    pub matched: Color,
+    /// Background of the entry under the cursor.
+    pub selected: Color,
+    /// Background of the entry under the mouse pointer.
+    pub hovered: Color,
+    pub background: Color,
+    /// Colors of the syntax highlighting in the preview.
+    pub keyword: Color,
+    pub string: Color,
+    pub number: Color,
+    pub comment: Color,
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Self {
+            logo: Color::Red,
+            header: Color::Red,
+            bracket: Color::Gray,
+            key: Color::Blue,
+            path: Color::DarkGray,
+            name: Color::Reset,
+            matched: Color::Yellow,
+            selected: Color::Indexed(237),
+            hovered: Color::Indexed(235),
+            background: Color::Reset,
+            keyword: Color::Magenta,
+            string: Color::Green,
+            number: Color::Cyan,
+            comment: Color::DarkGray,
+        }
+    }
+}
+
+impl Colors {
+    fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        let color = Color::from_str(value).map_err(|_| {
+            anyhow!(
+                "invalid color `{value}` for `{name}`, expected a color name, a 0-255 index or #rrggbb"
+            )
+        })?;
+        let field = match name {
+            "logo" => &mut self.logo,
+            "header" => &mut self.header,
+            "bracket" => &mut self.bracket,
+            "key" => &mut self.key,
+            "path" => &mut self.path,
+            "name" => &mut self.name,
+            "matched" => &mut self.matched,
+            "selected" => &mut self.selected,
+            "hovered" => &mut self.hovered,
+            "background" => &mut self.background,
+            "keyword" => &mut self.keyword,
+            "string" => &mut self.string,
+            "number" => &mut self.number,
+            "comment" => &mut self.comment,
+            _ => bail!("unknown color `{name}`"),
+        };
+        *field = color;
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Loads `$XDG_CONFIG_HOME/helix-startify/config.toml`, falling back to the
+    /// defaults when the file does not exist, and resolves the colors.
+    pub fn load() -> Result<Self> {
+        let mut config = match Self::path() {
+            Some(path) => Self::read(&path)?,
+            None => Self::default(),
+        };
+        let theme = match config.theme.as_str() {
+            "auto" => Theme::load_helix(),
+            "none" => None,
+            name => Theme::load(name),
+        };
+        config.colors = theme.map_or_else(Colors::default, |x| x.colors(Colors::default()));
+        for (name, value) in &config.color_overrides {
+            config.colors.set(name, value)?;
+        }
+        Ok(config)
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+        };
+        let config: Self = toml::from_str(&content)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        config
+            .validate()
+            .with_context(|| format!("invalid config {}", path.display()))?;
+        Ok(config)
+    }
+
+    fn path() -> Option<PathBuf> {
+        xdg_dir("XDG_CONFIG_HOME", ".config").map(|x| x.join("helix-startify/config.toml"))
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(editor) = &self.editor {
+            let args = editor::split(editor).context("invalid `editor`")?;
+            if args.is_empty() {
+                bail!("`editor` must not be empty");
+            }
+        }
+        if self.keys.chars().count() < 2 {
+            bail!("`keys` must contain at least 2 characters");
+        }
+        let mut seen = HashSet::new();
+        for c in self.keys.chars() {
+            if RESERVED_KEYS.contains(&c) || c.is_whitespace() || c.is_control() {
+                bail!("`keys` must not contain {c:?}");
+            }
+            if !seen.insert(c) {
+                bail!("`keys` contains {c:?} more than once");
+            }
+        }
+        let mut colors = Colors::default();
+        for (name, value) in &self.color_overrides {
+            colors.set(name, value)?;
+        }
+        if self.history_size < self.max_recents {
+            bail!(
+                "`history_size` ({}) must be at least `max_recents` ({})",
+                self.history_size,
+                self.max_recents
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the length of the jump labels when `count` entries are listed.
+    /// All labels share the same length, so none is a prefix of another.
+    pub fn label_len(&self, count: usize) -> usize {
+        let base = self.keys.chars().count();
+        let (mut len, mut capacity) = (1, base);
+        while capacity < count {
+            len += 1;
+            capacity = capacity.saturating_mul(base);
+        }
+        len
+    }
+
+    /// Returns the jump label of entry `idx` out of `count`.
+    pub fn label(&self, idx: usize, count: usize) -> String {
+        let keys: Vec<char> = self.keys.chars().collect();
+        let mut label = vec![keys[0]; self.label_len(count)];
+        let mut rest = idx;
+        for c in label.iter_mut().rev() {
+            *c = keys[rest % keys.len()];
+            rest /= keys.len();
+        }
+        label.into_iter().collect()
+    }
+
+    /// Returns the entry index of a complete jump label out of `count`.
+    pub fn label_index(&self, label: &str, count: usize) -> Option<usize> {
+        if label.chars().count() != self.label_len(count) {
+            return None;
+        }
+        let base = self.keys.chars().count();
+        let idx = label.chars().try_fold(0usize, |idx, c| {
+            let digit = self.keys.chars().position(|x| x == c)?;
+            idx.checked_mul(base)?.checked_add(digit)
+        })?;
+        (idx < count).then_some(idx)
+    }
+
+    pub fn is_key(&self, c: char) -> bool {
+        self.keys.contains(c)
+    }
+}
+
+/// Replaces a leading `~` with `$HOME`.
+pub fn expand_tilde(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), env::var_os("HOME")) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            PathBuf::from(home).join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Returns the XDG base directory named by `var`, or `$HOME/<fallback>` when it
+/// is unset or not absolute, as required by the spec.
+pub fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|x| x.is_absolute())
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|x| !x.is_empty())
+                .map(|x| PathBuf::from(x).join(fallback))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_round_trip() {
+        let config = Config::default();
+        for (count, len) in [(1, 1), (16, 1), (17, 2), (256, 2), (257, 3)] {
+            assert_eq!(config.label_len(count), len);
+            let mut seen = HashSet::new();
+            for idx in 0..count {
+                let label = config.label(idx, count);
+                assert_eq!(label.chars().count(), len);
+                assert_eq!(config.label_index(&label, count), Some(idx));
+                assert!(seen.insert(label));
+            }
+        }
+    }
+
+    #[test]
+    fn label_index_rejects_others() {
+        let config = Config::default();
+        assert_eq!(config.label_index("0", 17), None);
+        assert_eq!(config.label_index("000", 17), None);
+        assert_eq!(config.label_index("0z", 17), None);
+        // "11" would be entry 17 of 17
+        assert_eq!(config.label_index("11", 17), None);
+        assert_eq!(config.label_index("10", 17), Some(16));
+    }
+}
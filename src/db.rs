@@ -0,0 +1,529 @@
+use anyhow::{bail, Context, Result};
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::{Config, Sort};
+use crate::project;
+
+/// Current layout of `app.db`, bumped whenever a migration is added.
+const VERSION: u64 = 4;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Item {
+    pub path: String,
+    #[serde(default)]
+    pub kind: Kind,
+    /// Unix timestamp in seconds, 0 if the item was never opened.
+    #[serde(default)]
+    pub last_opened: u64,
+    #[serde(default)]
+    pub open_count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    #[default]
+    File,
+    Directory,
+}
+
+impl Kind {
+    fn of(path: &str) -> Self {
+        if Path::new(path).is_dir() {
+            Self::Directory
+        } else {
+            Self::File
+        }
+    }
+}
+
+impl Item {
+    pub fn new(path: String) -> Self {
+        Self {
+            kind: Kind::of(&path),
+            path,
+            last_opened: 0,
+            open_count: 0,
+            line: None,
+            column: None,
+            label: None,
+        }
+    }
+
+    /// Open count weighted by the age of the last open, like zoxide does.
+    fn frecency(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_opened);
+        let weight = match age {
+            0..=3599 => 4.0,
+            3600..=86399 => 2.0,
+            86400..=604799 => 0.5,
+            _ => 0.25,
+        };
+        self.open_count as f64 * weight
+    }
+
+    fn touch(&mut self) {
+        self.kind = Kind::of(&self.path);
+        self.last_opened = now();
+        self.open_count += 1;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// Recents under the directory the start screen was opened in.
+    ProjectRecents,
+    Recents,
+    /// Git repositories of the recents.
+    Projects,
+    Sessions,
+    Bookmarks,
+}
+
+impl Section {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ProjectRecents => "project recents",
+            Self::Recents => "recents",
+            Self::Projects => "projects",
+            Self::Sessions => "sessions",
+            Self::Bookmarks => "bookmarks",
+        }
+    }
+
+    /// Returns what identifies `item` in the section: the name of a session,
+    /// the path otherwise.
+    pub fn key(self, item: &Item) -> &str {
+        match self {
+            Self::Sessions => item.label.as_deref().unwrap_or_default(),
+            _ => &item.path,
+        }
+    }
+
+    /// Returns the section the items of this one are stored in.
+    pub fn stored_in(self) -> Self {
+        match self {
+            Self::ProjectRecents => Self::Recents,
+            section => section,
+        }
+    }
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct App {
+    pub recents: VecDeque<Item>,
+    /// Roots of the git repositories of opened paths, most recent first.
+    #[serde(default)]
+    pub projects: VecDeque<Item>,
+    /// Most recently used first.
+    #[serde(default)]
+    pub sessions: Vec<Session>,
+    pub bookmarks: Vec<Item>,
+}
+
+/// Files opened together from a working directory.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Listed like any item, labeled with the name of the session and with the
+    /// working directory as path.
+    #[serde(flatten)]
+    pub item: Item,
+    pub files: Vec<Item>,
+}
+
+impl Session {
+    pub fn name(&self) -> &str {
+        Section::Sessions.key(&self.item)
+    }
+}
+
+impl App {
+    /// Records an open of `path`, moving it to the front of the recents. The
+    /// position is remembered when given, otherwise the last one is kept.
+    pub fn open(
+        &mut self,
+        path: &str,
+        line: Option<usize>,
+        column: Option<usize>,
+        history_size: usize,
+    ) {
+        let mut item = match self.recents.iter().position(|x| x.path == path) {
+            Some(pos) => self.recents.remove(pos).unwrap(),
+            None => Item::new(path.to_owned()),
+        };
+        if line.is_some() {
+            (item.line, item.column) = (line, column);
+        }
+        item.touch();
+        self.recents.push_front(item);
+        self.recents.truncate(history_size);
+        for item in self.bookmarks.iter_mut().filter(|x| x.path == path) {
+            item.touch();
+        }
+        if let Some(root) = project::git_root(Path::new(path)) {
+            self.open_project(&root.to_string_lossy(), history_size);
+        }
+    }
+
+    /// Records an open of the repository at `root`, moving it to the front of
+    /// the projects.
+    pub fn open_project(&mut self, root: &str, history_size: usize) {
+        let mut item = match self.projects.iter().position(|x| x.path == root) {
+            Some(pos) => self.projects.remove(pos).unwrap(),
+            None => Item::new(root.to_owned()),
+        };
+        item.touch();
+        self.projects.push_front(item);
+        self.projects.truncate(history_size);
+    }
+
+    /// Stores the session `name`, replacing an older one of the same name.
+    pub fn save_session(&mut self, name: &str, dir: &str, files: Vec<Item>) {
+        self.sessions.retain(|x| x.name() != name);
+        let mut item = Item::new(dir.to_owned());
+        item.label = Some(name.to_owned());
+        self.sessions.insert(0, Session { item, files });
+    }
+
+    /// Records an open of the session `name` and returns it.
+    pub fn open_session(&mut self, name: &str) -> Option<Session> {
+        let pos = self.sessions.iter().position(|x| x.name() == name)?;
+        let mut session = self.sessions.remove(pos);
+        session.item.touch();
+        self.sessions.insert(0, session.clone());
+        Some(session)
+    }
+
+    /// Returns the sections listed on the start screen with their items, in
+    /// display order. The project section lists the recents under `scope` and
+    /// is left out when there are none.
+    pub fn listed(&self, config: &Config, scope: Option<&Path>) -> Vec<(Section, Vec<&Item>)> {
+        let mut sections = Vec::new();
+        if let Some(scope) = scope {
+            let project: Vec<&Item> = self
+                .recents
+                .iter()
+                .filter(|x| {
+                    Path::new(&x.path)
+                        .strip_prefix(scope)
+                        .is_ok_and(|x| x != Path::new(""))
+                })
+                .take(config.max_project_recents)
+                .collect();
+            if !project.is_empty() {
+                sections.push((Section::ProjectRecents, project));
+            }
+        }
+        sections.push((
+            Section::Recents,
+            self.recents.iter().take(config.max_recents).collect(),
+        ));
+        let projects: Vec<&Item> = self.projects.iter().take(config.max_projects).collect();
+        if !projects.is_empty() {
+            sections.push((Section::Projects, projects));
+        }
+        if !self.sessions.is_empty() {
+            let sessions = self.sessions.iter().map(|x| &x.item).collect();
+            sections.push((Section::Sessions, sessions));
+        }
+        sections.push((Section::Bookmarks, self.bookmarks.iter().collect()));
+        sections
+    }
+
+    /// Removes the item with the `key` of `section` from its storage.
+    pub fn remove(&mut self, section: Section, key: &str) -> Option<Item> {
+        match section.stored_in() {
+            Section::Sessions => {
+                let pos = self.sessions.iter().position(|x| x.name() == key)?;
+                Some(self.sessions.remove(pos).item)
+            }
+            Section::Bookmarks => {
+                let pos = self.bookmarks.iter().position(|x| x.path == key)?;
+                Some(self.bookmarks.remove(pos))
+            }
+            Section::Projects => {
+                let pos = self.projects.iter().position(|x| x.path == key)?;
+                self.projects.remove(pos)
+            }
+            _ => {
+                let pos = self.recents.iter().position(|x| x.path == key)?;
+                self.recents.remove(pos)
+            }
+        }
+    }
+
+    /// Removes the recents, projects, bookmarks and sessions whose path no
+    /// longer exists, returning how many were removed.
+    pub fn prune(&mut self) -> usize {
+        let exists = |x: &Item| Path::new(&x.path).exists();
+        let before = self.paths().count();
+        self.recents.retain(exists);
+        self.projects.retain(exists);
+        self.sessions.retain(|x| exists(&x.item));
+        self.bookmarks.retain(exists);
+        before - self.paths().count()
+    }
+
+    /// Returns every path listed on the start screen.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.recents
+            .iter()
+            .chain(&self.projects)
+            .chain(self.sessions.iter().map(|x| &x.item))
+            .chain(&self.bookmarks)
+            .map(|x| x.path.as_str())
+    }
+
+    /// Orders the recents by `sort`, best first. Ties keep their current order.
+    pub fn rank_recents(&mut self, sort: Sort) {
+        let now = now();
+        let recents = self.recents.make_contiguous();
+        match sort {
+            Sort::Mru => recents.sort_by_key(|x| std::cmp::Reverse(x.last_opened)),
+            Sort::Frecency => recents.sort_by(|a, b| b.frecency(now).total_cmp(&a.frecency(now))),
+            Sort::Frequency => {
+                recents.sort_by_key(|x| std::cmp::Reverse((x.open_count, x.last_opened)))
+            }
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+        };
+        if data.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let value = match serde_json::from_str::<Value>(&data) {
+            Ok(value) => value,
+            Err(e) => return Self::recover(path, e.into()),
+        };
+        let version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+        if version > VERSION {
+            bail!(
+                "{} has version {version}, but this helix-startify only supports up to {VERSION}",
+                path.display()
+            );
+        }
+        match migrate(value, version).and_then(|x| Ok(serde_json::from_value(x)?)) {
+            Ok(app) => Ok(app),
+            Err(e) => Self::recover(path, e),
+        }
+    }
+
+    /// Moves an unreadable database aside instead of silently discarding it.
+    fn recover(path: &Path, err: anyhow::Error) -> Result<Self> {
+        let backup = with_suffix(path, &format!(".corrupt-{}", now()));
+        fs::rename(path, &backup)
+            .with_context(|| format!("failed to back up corrupt {}", path.display()))?;
+        eprintln!(
+            "warning: {} is corrupt ({err:#}), moved it to {} and starting fresh",
+            path.display(),
+            backup.display()
+        );
+        Ok(Self::default())
+    }
+
+    /// Applies `f` to the current on-disk state while holding an exclusive lock
+    /// on the database, so concurrent processes never overwrite each other's
+    /// changes. Nothing is written if `f` fails.
+    pub fn update<T>(path: &Path, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if let Some(dir) = path.parent().filter(|x| !x.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        let lock_path = with_suffix(path, ".lock");
+        let lock = File::options()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)
+            .with_context(|| format!("failed to open {}", lock_path.display()))?;
+        lock.lock()
+            .with_context(|| format!("failed to lock {}", lock_path.display()))?;
+
+        let mut app = Self::load(path)?;
+        let res = f(&mut app)?;
+        app.save(path)?;
+        Ok(res)
+    }
+
+    /// Writes to a temporary file next to `path` and renames it into place, so
+    /// readers only ever observe a complete database.
+    fn save(&self, path: &Path) -> Result<()> {
+        let tmp_path = with_suffix(path, &format!(".tmp.{}", process::id()));
+        let res = (|| -> io::Result<()> {
+            let mut value = serde_json::to_value(self)?;
+            value["version"] = VERSION.into();
+            let mut file = File::create(&tmp_path)?;
+            file.write_all(serde_json::to_string(&value)?.as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, path)
+        })();
+        if let Err(e) = res {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e).with_context(|| format!("failed to write {}", path.display()));
+        }
+        if let Some(dir) = path.parent().filter(|x| !x.as_os_str().is_empty()) {
+            let _ = File::open(dir).and_then(|x| x.sync_all());
+        }
+        Ok(())
+    }
+}
+
+/// Upgrades a database of the given `version` to the current layout, one
+/// version at a time.
+fn migrate(mut value: Value, version: u64) -> Result<Value> {
+    if version < 1 {
+        // v0 stored every item as a bare path string
+        for key in ["recents", "bookmarks"] {
+            if let Some(Value::Array(items)) = value.get_mut(key) {
+                for item in items {
+                    if let Value::String(path) = item {
+                        *item = serde_json::json!({ "path": path });
+                    }
+                }
+            }
+        }
+    }
+    if version < 2 {
+        // v1 items only had a path, the file system tells us the kind
+        for key in ["recents", "bookmarks"] {
+            if let Some(Value::Array(items)) = value.get_mut(key) {
+                for item in items {
+                    let kind = item.get("path").and_then(Value::as_str).map(Kind::of);
+                    if let (Some(kind), Value::Object(item)) = (kind, item) {
+                        item.insert("kind".to_owned(), serde_json::to_value(kind)?);
+                    }
+                }
+            }
+        }
+    }
+    if version < 3 {
+        // paths were stored as given, merge the ones naming the same file
+        for key in ["recents", "bookmarks"] {
+            if let Some(Value::Array(items)) = value.get_mut(key) {
+                let mut merged: Vec<Value> = Vec::new();
+                for mut item in items.drain(..) {
+                    let Some(path) = item.get("path").and_then(Value::as_str) else {
+                        continue;
+                    };
+                    let path = Value::from(normalize(Path::new(path)));
+                    item["path"] = path.clone();
+                    match merged.iter_mut().find(|x| x["path"] == path) {
+                        // keep the first, most recent one, with the combined stats
+                        Some(first) if key == "recents" => {
+                            let count = |x: &Value| x.get("open_count").and_then(Value::as_u64);
+                            let last = |x: &Value| x.get("last_opened").and_then(Value::as_u64);
+                            first["open_count"] =
+                                (count(first).unwrap_or(0) + count(&item).unwrap_or(0)).into();
+                            first["last_opened"] = last(first).max(last(&item)).unwrap_or(0).into();
+                        }
+                        Some(_) => {}
+                        None => merged.push(item),
+                    }
+                }
+                *items = merged;
+            }
+        }
+    }
+    if version < 4 {
+        // projects were not tracked, the recents tell which were used
+        let mut projects: Vec<Value> = Vec::new();
+        if let Some(Value::Array(items)) = value.get("recents") {
+            for item in items {
+                let path = item.get("path").and_then(Value::as_str).map(Path::new);
+                if let Some(root) = path.and_then(project::git_root) {
+                    let root = Value::from(root.to_string_lossy());
+                    if projects.iter().all(|x| x["path"] != root) {
+                        projects.push(serde_json::json!({ "path": root, "kind": Kind::Directory }));
+                    }
+                }
+            }
+        }
+        if let Value::Object(app) = &mut value {
+            app.insert("projects".to_owned(), projects.into());
+        }
+    }
+    Ok(value)
+}
+
+/// Makes `path` absolute against the current directory, resolving symlinks
+/// if asked to and the path exists.
+pub fn canonicalize(path: &Path, resolve_symlinks: bool) -> Result<String> {
+    let path = env::current_dir()
+        .context("failed to get the current directory")?
+        .join(path);
+    match fs::canonicalize(&path) {
+        Ok(path) if resolve_symlinks => Ok(path.to_string_lossy().into_owned()),
+        _ => Ok(normalize(&path)),
+    }
+}
+
+/// Removes `.`, `..` and trailing slashes from `path` without touching the
+/// file system, so every file has a single entry.
+fn normalize(path: &Path) -> String {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            component => normalized.push(component),
+        }
+    }
+    normalized.to_string_lossy().into_owned()
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |x| x.as_secs())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut s = path.as_os_str().to_owned();
+    s.push(suffix);
+    PathBuf::from(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_baseline() {
+        let value = serde_json::json!({
+            "recents": ["/a/./b", "/a/b"],
+            "bookmarks": ["/c/"],
+        });
+        let app: App = serde_json::from_value(migrate(value, 0).unwrap()).unwrap();
+
+        let recents: Vec<&str> = app.recents.iter().map(|x| x.path.as_str()).collect();
+        assert_eq!(recents, ["/a/b"]);
+        assert!(app.recents[0].kind == Kind::File);
+        assert_eq!(app.recents[0].open_count, 0);
+        let bookmarks: Vec<&str> = app.bookmarks.iter().map(|x| x.path.as_str()).collect();
+        assert_eq!(bookmarks, ["/c"]);
+        assert!(app.projects.is_empty());
+        assert!(app.sessions.is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::env;
+use std::fs;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::db::Item;
+
+/// Editors tried in order when neither the config nor the environment sets one,
+/// some distributions ship Helix as `helix`.
+const DEFAULT_EDITORS: &[&str] = &["hx", "helix"];
+
+/// A file or directory passed to the editor.
+pub struct Target {
+    pub path: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl Target {
+    pub fn new(path: String) -> Self {
+        Self {
+            path,
+            line: None,
+            column: None,
+        }
+    }
+
+    /// Parses `file`, `file:line` or `file:line:col` like Helix does, an
+    /// existing file keeps its name even if it looks like it has a position.
+    pub fn parse(arg: String) -> Self {
+        if Path::new(&arg).exists() {
+            return Self::new(arg);
+        }
+        let number = |s: &str| s.parse::<usize>().ok().filter(|&x| x > 0);
+        let mut parts = arg.rsplitn(3, ':');
+        let (last, middle, rest) = (parts.next(), parts.next(), parts.next());
+        let (path, line, column) = match (rest, middle.and_then(number), last.and_then(number)) {
+            (Some(path), Some(line), Some(column)) => (path, Some(line), Some(column)),
+            (_, _, Some(line)) => (arg.rsplit_once(':').unwrap().0, Some(line), None),
+            _ => return Self::new(arg),
+        };
+        if path.is_empty() {
+            return Self::new(arg);
+        }
+        Self {
+            path: path.to_owned(),
+            line,
+            column,
+        }
+    }
+
+    /// Returns the path with the position appended, as Helix accepts it.
+    fn with_position(&self) -> String {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{}:{line}:{column}", self.path),
+            (Some(line), None) => format!("{}:{line}", self.path),
+            _ => self.path.clone(),
+        }
+    }
+}
+
+impl From<&Item> for Target {
+    fn from(item: &Item) -> Self {
+        Self {
+            path: item.path.clone(),
+            line: item.line,
+            column: item.column,
+        }
+    }
+}
+
+/// Replaces the process with the editor opening `targets`. Only returns if the
+/// editor could not be started.
+pub fn exec(editor: Option<&str>, flags: &[String], targets: &[Target]) -> anyhow::Error {
+    let mut command = match command(editor, flags, targets) {
+        Ok(command) => command,
+        Err(e) => return e,
+    };
+    let err = command.exec();
+    anyhow!(err).context(format!(
+        "failed to run {:?}",
+        command.get_program().to_string_lossy()
+    ))
+}
+
+/// Builds the editor command from `editor`, `$HX`, `hx` or `helix` and then
+/// `$EDITOR`, the first one set or installed. `flags` follow the fixed
+/// arguments of the command verbatim. Arguments using `{path}`, `{line}` or
+/// `{col}` are repeated for every target after them, the paths are appended
+/// with their positions if none does.
+pub fn command(editor: Option<&str>, flags: &[String], targets: &[Target]) -> Result<Command> {
+    let template = editor
+        .map(str::to_owned)
+        .or_else(|| from_env("HX"))
+        .or_else(|| {
+            let program = DEFAULT_EDITORS.iter().find(|x| which(x).is_some())?;
+            Some(program.to_string())
+        })
+        // $EDITOR is often another editor, not taking the flags of Helix
+        .or_else(|| from_env("EDITOR"))
+        .with_context(|| {
+            format!(
+                "neither {} is installed, set `editor` in the config or $HX",
+                DEFAULT_EDITORS.join(" nor ")
+            )
+        })?;
+    let mut args = split(&template)?.into_iter();
+    let program = args.next().context("the editor command is empty")?;
+    let found = if program.contains('/') {
+        Some(PathBuf::from(&program)).filter(|x| x.is_file())
+    } else {
+        which(&program)
+    };
+    let Some(found) = found else {
+        bail!("editor {program:?} not found, set `editor` in the config or $HX");
+    };
+    // running ourselves again would loop forever
+    let exe = env::current_exe().and_then(fs::canonicalize).ok();
+    if exe.is_some() && fs::canonicalize(found).ok() == exe {
+        bail!("editor {program:?} is helix-startify itself, set `editor` in the config or $HX");
+    }
+
+    let args: Vec<String> = args.collect();
+    let placeholders = ["{path}", "{line}", "{col}"];
+    let (templates, fixed): (Vec<&String>, Vec<&String>) = args
+        .iter()
+        .partition(|x| placeholders.iter().any(|p| x.contains(p)));
+    let mut command = Command::new(&program);
+    // wrappers like `flatpak run <app>` take the flags after their own arguments
+    command.args(fixed);
+    command.args(flags);
+    for arg in &templates {
+        for target in targets {
+            command.arg(
+                arg.replace("{path}", &target.path)
+                    .replace("{line}", &target.line.unwrap_or(1).to_string())
+                    .replace("{col}", &target.column.unwrap_or(1).to_string()),
+            );
+        }
+    }
+    if templates.is_empty() {
+        command.args(targets.iter().map(Target::with_position));
+    }
+    Ok(command)
+}
+
+fn from_env(name: &str) -> Option<String> {
+    let value = env::var_os(name)?.to_string_lossy().into_owned();
+    (!value.trim().is_empty()).then_some(value)
+}
+
+/// Splits `s` at whitespace like a shell, honoring single and double quotes
+/// and backslash escapes.
+pub fn split(s: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut quote = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                let escaped = chars.next().context("trailing backslash")?;
+                arg.get_or_insert_with(String::new).push(escaped);
+            }
+            (Some(_), c) => arg.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                arg.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => args.extend(arg.take()),
+            (None, c) => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(q) = quote {
+        bail!("unterminated {q} quote");
+    }
+    args.extend(arg);
+    Ok(args)
+}
+
+/// Returns the first executable file named `program` in `$PATH`.
+pub fn which(program: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|x| x.join(program))
+        .find(|x| x.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(arg: &str) -> (String, Option<usize>, Option<usize>) {
+        let target = Target::parse(arg.to_owned());
+        (target.path, target.line, target.column)
+    }
+
+    #[test]
+    fn parse_positions() {
+        assert_eq!(parse("foo.rs"), ("foo.rs".into(), None, None));
+        assert_eq!(parse("foo.rs:10"), ("foo.rs".into(), Some(10), None));
+        assert_eq!(parse("foo.rs:10:5"), ("foo.rs".into(), Some(10), Some(5)));
+        assert_eq!(parse("a:b:10"), ("a:b".into(), Some(10), None));
+        assert_eq!(parse("foo.rs:0"), ("foo.rs:0".into(), None, None));
+        assert_eq!(parse(":10"), (":10".into(), None, None));
+    }
+
+    #[test]
+    fn split_quoted() {
+        assert_eq!(
+            split("flatpak run  com.helix_editor.Helix").unwrap(),
+            ["flatpak", "run", "com.helix_editor.Helix"]
+        );
+        assert_eq!(
+            split(r#"'/opt/my editor/hx' "{path}:{line}" a\ b"#).unwrap(),
+            ["/opt/my editor/hx", "{path}:{line}", "a b"]
+        );
+        assert_eq!(
+            split(r#"a "b\"c" 'd\e' """#).unwrap(),
+            ["a", "b\"c", "d\\e", ""]
+        );
+        assert!(split("hx 'a").is_err());
+        assert!(split("hx a\\").is_err());
+    }
+
+    #[test]
+    fn parse_existing_file_with_colon() {
+        let path = env::temp_dir().join(format!("helix-startify-{}:10", std::process::id()));
+        fs::write(&path, "").unwrap();
+        let arg = path.to_string_lossy().into_owned();
+        let parsed = parse(&arg);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(parsed, (arg, None, None));
+    }
+}
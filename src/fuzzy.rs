@@ -0,0 +1,78 @@
+/// Matches `query` as a subsequence of `text`, returning a score (higher is
+/// better) and the char indices of `text` that matched. Matching is case
+/// insensitive unless the query contains an uppercase letter.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let smart_case = query.chars().any(char::is_uppercase);
+    let eq = |a: char, b: char| {
+        if smart_case {
+            a == b
+        } else {
+            a.to_lowercase().eq(b.to_lowercase())
+        }
+    };
+    let q: Vec<char> = query.chars().filter(|x| !x.is_whitespace()).collect();
+    let t: Vec<char> = text.chars().collect();
+    if q.is_empty() {
+        return Some((0, Vec::new()));
+    }
+    if q.len() > t.len() {
+        return None;
+    }
+
+    let name_start = t.iter().rposition(|&x| x == '/').map_or(0, |x| x + 1);
+    let bonus = |j: usize| {
+        let boundary = match j.checked_sub(1).map(|x| t[x]) {
+            None | Some('/') => 10,
+            Some('-' | '_' | '.' | ' ') => 8,
+            Some(prev) if prev.is_lowercase() && t[j].is_uppercase() => 6,
+            _ => 0,
+        };
+        boundary + if j >= name_start { 4 } else { 0 }
+    };
+
+    // score[i][j] is the best score with q[i] matched at t[j], from[i][j] the
+    // position q[i - 1] was matched at to get there
+    let (n, m) = (q.len(), t.len());
+    let mut score = vec![vec![None::<i64>; m]; n];
+    let mut from = vec![vec![0; m]; n];
+    for j in 0..m {
+        if eq(q[0], t[j]) {
+            score[0][j] = Some(16 + bonus(j));
+        }
+    }
+    for i in 1..n {
+        let mut gap: Option<(i64, usize)> = None;
+        for j in i..m {
+            if j >= 2 {
+                gap = gap.map(|(s, k)| (s - 1, k));
+                if let Some(s) = score[i - 1][j - 2] {
+                    if gap.is_none_or(|(g, _)| s - 1 > g) {
+                        gap = Some((s - 1, j - 2));
+                    }
+                }
+            }
+            if !eq(q[i], t[j]) {
+                continue;
+            }
+            let consecutive = score[i - 1][j - 1].map(|s| (s + 8, j - 1));
+            let best = match (consecutive, gap) {
+                (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                (a, b) => a.or(b),
+            };
+            if let Some((s, k)) = best {
+                score[i][j] = Some(s + 16 + bonus(j));
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut j, best) = (0..m)
+        .filter_map(|j| score[n - 1][j].map(|s| (j, s)))
+        .max_by_key(|&(_, s)| s)?;
+    let mut indices = vec![0; n];
+    for i in (0..n).rev() {
+        indices[i] = j;
+        j = from[i][j];
+    }
+    Some((best, indices))
+}
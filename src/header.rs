@@ -0,0 +1,107 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{self, Extra, HeaderConfig};
+
+const HELIX: &str = include_str!("../logo");
+
+const SMALL: &str = r" _          _ _
+| |__   ___| (_)_  __
+| '_ \ / _ \ | \ \/ /
+| | | |  __/ | |>  <
+|_| |_|\___|_|_/_/\_\";
+
+const QUOTES: &[&str] = &[
+    "Simplicity is prerequisite for reliability. - Edsger W. Dijkstra",
+    "Programs must be written for people to read. - Harold Abelson",
+    "Make it work, make it right, make it fast. - Kent Beck",
+    "Premature optimization is the root of all evil. - Donald Knuth",
+    "Talk is cheap. Show me the code. - Linus Torvalds",
+    "The best code is no code at all. - Jeff Atwood",
+    "First, solve the problem. Then, write the code. - John Johnson",
+    "Deleted code is debugged code. - Jeff Sickel",
+    "Perfection is achieved when there is nothing left to take away. - Antoine de Saint-Exupery",
+    "Any fool can write code that a computer can understand. - Martin Fowler",
+];
+
+/// Text shown above the lists, resolved once at startup.
+pub struct Header {
+    pub logo: String,
+    /// Shown instead of the logo when it does not fit, `None` without a logo.
+    pub title: Option<String>,
+    pub extras: Vec<String>,
+}
+
+impl Header {
+    pub fn load(config: &HeaderConfig) -> Result<Self> {
+        let logo = match config.logo.as_str() {
+            "helix" => HELIX.to_owned(),
+            "small" => SMALL.to_owned(),
+            "none" => String::new(),
+            path => {
+                let path = config::expand_tilde(path);
+                fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read header {}", path.display()))?
+            }
+        };
+        let title = match config.logo.as_str() {
+            "helix" | "small" => Some("helix".to_owned()),
+            _ => logo
+                .lines()
+                .map(str::trim)
+                .find(|x| !x.is_empty())
+                .map(str::to_owned),
+        };
+        let extras = config
+            .extras
+            .iter()
+            .filter_map(|x| match x {
+                Extra::Date => command_output("date", &["+%A, %-d %B %Y"]),
+                Extra::Quote => Some(quote()),
+                Extra::Fortune => command_output("fortune", &["-s"]).or_else(|| Some(quote())),
+            })
+            .collect();
+        Ok(Self {
+            logo: logo.trim_end().to_owned(),
+            title,
+            extras,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.lines().map(|x| x.chars().count()).max().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.lines().count()
+    }
+
+    /// Returns the logo lines, a blank line and the extra lines.
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        let gap = (!self.logo.is_empty() && !self.extras.is_empty()).then_some("");
+        self.logo
+            .lines()
+            .chain(gap)
+            .chain(self.extras.iter().map(String::as_str))
+    }
+}
+
+fn quote() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |x| x.subsec_nanos());
+    QUOTES[nanos as usize % QUOTES.len()].to_owned()
+}
+
+/// Runs `program` and returns its output joined into a single line, or `None`
+/// if it is not installed or fails.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    let output = String::from_utf8(output.stdout)
+        .ok()
+        .filter(|_| output.status.success())?;
+    let line = output.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!line.is_empty()).then_some(line)
+}
@@ -1,86 +1,138 @@
-use anyhow::Result;
-use std::collections::VecDeque;
+use anyhow::{bail, Context, Result};
 use std::env;
-use std::fs::File;
-use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
 use std::{
-    fs,
-    io::{self, Write},
-    process::Command,
+    io,
     time::{Duration, Instant},
 };
 
-use clap::{arg, command};
+use clap::{arg, command, ArgAction, ArgMatches};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::{prelude::*, widgets::*};
-use serde::{Deserialize, Serialize};
-
-#[derive(PartialEq, Eq, Serialize, Deserialize)]
-struct Item(String);
-
-impl Item {
-    fn as_line(&self, c: char) -> Line {
-        let (path, name) = self.0.rsplit_once('/').unwrap();
-        Line::from(vec![
-            Span::styled("[", Style::default().fg(Color::Gray)),
-            Span::styled(c.to_string(), Style::default().fg(Color::Blue)),
-            Span::styled("]  ", Style::default().fg(Color::Gray)),
-            Span::styled(path.to_owned() + "/", Style::default().fg(Color::DarkGray)),
-            Span::styled(name, Style::default()),
-        ])
-    }
-}
+use ratatui::prelude::*;
+
+use config::Config;
+use db::{App, Item, Section};
+use editor::Target;
+use header::Header;
+use ui::{ui, Prompt, State};
+
+mod config;
+mod db;
+mod editor;
+mod fuzzy;
+mod header;
+mod preview;
+mod project;
+mod theme;
+mod ui;
 
-#[derive(Default, Serialize, Deserialize)]
-struct App {
-    recents: VecDeque<Item>,
-    bookmarks: Vec<Item>,
+/// Flags of Helix taking a value, which must not be mistaken for a path.
+const HELIX_VALUE_FLAGS: &[&str] = &[
+    "-c",
+    "--config",
+    "--log",
+    "-w",
+    "--working-dir",
+    "-g",
+    "--grammar",
+];
+
+/// Flags of Helix taking an optional value, taken as the value unless it
+/// looks like another flag.
+const HELIX_OPTIONAL_VALUE_FLAGS: &[&str] = &["--health"];
+
+enum Action {
+    Quit,
+    /// Opens the target, listed in the section.
+    Open(Section, Target),
+    /// Opens all files of the named session.
+    OpenSession(String),
 }
 
-impl App {
-    fn save(&self, path: &str) -> Result<()> {
-        let data = serde_json::to_string(self)?;
-        let mut file = File::options()
-            .write(true)
-            .truncate(true)
-            .open(format!("{path}/app.db"))?;
-        write!(file, "{}", data)?;
-        Ok(())
+impl Action {
+    /// Returns the action opening `item` listed in `section`.
+    fn open(section: Section, item: &Item) -> Self {
+        match section {
+            Section::Sessions => Self::OpenSession(section.key(item).to_owned()),
+            _ => Self::Open(section, item.into()),
+        }
     }
 }
 
 fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
-    mut app: App,
+    mut state: State,
+    config: &Config,
+    db_path: &Path,
     tick_rate: Duration,
-) -> Result<Option<String>> {
+) -> Result<Option<Action>> {
     let mut last_tick = Instant::now();
     loop {
-        terminal.draw(|f| ui(f, &mut app))?;
+        terminal.draw(|f| ui(f, &mut state, config))?;
 
         let timeout = tick_rate.saturating_sub(last_tick.elapsed());
         if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
-                        KeyCode::Char(c @ '0'..='f') => {
-                            let idx = c.to_digit(16).unwrap() as usize;
-                            if let Some(path) = app.recents.get(idx) {
-                                return Ok(Some(path.0.clone()));
-                            }
-                            let idx = idx - app.recents.len();
-                            if let Some(path) = app.bookmarks.get(idx) {
-                                return Ok(Some(path.0.clone()));
-                            }
+            let action = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    state.message = None;
+                    // keys may scroll or filter the list away from the pointer
+                    state.hovered = None;
+                    handle_key(&mut state, key, config, db_path)?
+                }
+                Event::Mouse(mouse) => {
+                    let hit = state.entry_at(mouse.column, mouse.row);
+                    match mouse.kind {
+                        MouseEventKind::Down(MouseButton::Left) => hit.and_then(|idx| {
+                            state.selected = idx;
+                            let entry = state.selected_entry(config)?;
+                            Some(Action::open(entry.section, entry.item))
+                        }),
+                        MouseEventKind::ScrollDown => {
+                            state.move_selection(1, config);
+                            state.hovered = None;
+                            None
+                        }
+                        MouseEventKind::ScrollUp => {
+                            state.move_selection(-1, config);
+                            state.hovered = None;
+                            None
+                        }
+                        MouseEventKind::Moved => {
+                            state.hovered = hit;
+                            None
                         }
-                        _ => {}
+                        _ => None,
                     }
                 }
+                _ => None,
+            };
+            let path = match &action {
+                Some(Action::Open(_, target)) => Some(&target.path),
+                Some(Action::OpenSession(name)) => state
+                    .app
+                    .sessions
+                    .iter()
+                    .find(|x| x.name() == name)
+                    .map(|x| &x.item.path),
+                _ => None,
+            };
+            if let Some(path) = path.filter(|x| !Path::new(x).exists()) {
+                state.message = Some(format!(
+                    "{path} no longer exists, P removes missing entries"
+                ));
+                continue;
+            }
+            match action {
+                Some(Action::Quit) => return Ok(None),
+                Some(action) => return Ok(Some(action)),
+                None => {}
             }
         }
         if last_tick.elapsed() >= tick_rate {
@@ -89,99 +141,473 @@ fn run_app<B: Backend>(
     }
 }
 
-fn ui(f: &mut Frame, app: &mut App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Length(13 + 5), Constraint::Min(0)])
-        .split(f.size());
-
-    let logo = fs::read_to_string("./logo").unwrap();
-    let logo_width = logo.lines().map(|x| x.len()).max().unwrap();
-    let left_pad = (chunks[0].width - logo_width as u16) / 2;
+fn handle_key(
+    state: &mut State,
+    key: KeyEvent,
+    config: &Config,
+    db_path: &Path,
+) -> Result<Option<Action>> {
+    let code = key.code;
+    if let Some(prompt) = &mut state.prompt {
+        match (prompt, code) {
+            (Prompt::Delete(section, path), KeyCode::Char('y')) => {
+                let (section, path) = (*section, path.clone());
+                state.update(db_path, config, |app| {
+                    app.remove(section, &path);
+                    Ok(())
+                })?;
+                state.message = Some(format!("Deleted {path}"));
+            }
+            (Prompt::Prune(_), KeyCode::Char('y')) => {
+                let mut count = 0;
+                state.update(db_path, config, |app| {
+                    count = app.prune();
+                    Ok(())
+                })?;
+                state.message = Some(format!("Removed {}", ui::missing_entries(count)));
+            }
+            (Prompt::Label(path, input), KeyCode::Enter) => {
+                let path = path.clone();
+                let label = Some(input.trim().to_owned()).filter(|x| !x.is_empty());
+                state.update(db_path, config, |app| {
+                    let items = app.recents.iter_mut().chain(&mut app.projects);
+                    for item in items.chain(&mut app.bookmarks) {
+                        if item.path == path {
+                            item.label = label.clone();
+                        }
+                    }
+                    Ok(())
+                })?;
+            }
+            (Prompt::Label(_, input), KeyCode::Backspace) => {
+                input.pop();
+                return Ok(None);
+            }
+            (Prompt::Label(_, input), KeyCode::Char(c)) => {
+                input.push(c);
+                return Ok(None);
+            }
+            (Prompt::Label(..), KeyCode::Esc) | (Prompt::Delete(..) | Prompt::Prune(_), _) => {}
+            (Prompt::Label(..), _) => return Ok(None),
+        }
+        state.prompt = None;
+        return Ok(None);
+    }
 
-    f.render_widget(
-        Paragraph::new(Text::styled(logo, Style::default().fg(Color::Red)))
-            .block(Block::default().padding(Padding::new(left_pad, 0, 5, 0))),
-        chunks[0],
-    );
+    let pending_g = std::mem::take(&mut state.pending_g);
+    let mut pending_label = std::mem::take(&mut state.pending_label);
+    let page = state.page as isize;
+    match code {
+        KeyCode::Up => state.move_selection(-1, config),
+        KeyCode::Down => state.move_selection(1, config),
+        KeyCode::PageUp => state.move_selection(-2 * page, config),
+        KeyCode::PageDown => state.move_selection(2 * page, config),
+        KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            state.move_selection(-page, config);
+            return Ok(None);
+        }
+        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            state.move_selection(page, config);
+            return Ok(None);
+        }
+        KeyCode::Enter => {
+            if let Some(entry) = state.selected_entry(config) {
+                return Ok(Some(Action::open(entry.section, entry.item)));
+            }
+        }
+        _ => {}
+    }
+    if let Some(query) = &mut state.filter {
+        match code {
+            KeyCode::Esc => {
+                state.filter = None;
+                state.selected = 0;
+            }
+            KeyCode::Backspace => {
+                if query.is_empty() {
+                    state.filter = None;
+                } else {
+                    query.pop();
+                }
+                state.selected = 0;
+            }
+            KeyCode::Char(c) => {
+                query.push(c);
+                state.selected = 0;
+            }
+            _ => {}
+        }
+        return Ok(None);
+    }
+    match code {
+        KeyCode::Esc if !pending_label.is_empty() => {}
+        KeyCode::Esc | KeyCode::Char('q') => return Ok(Some(Action::Quit)),
+        KeyCode::Char('/') => {
+            state.filter = Some(String::new());
+            state.selected = 0;
+        }
+        KeyCode::Char('j') => state.move_selection(1, config),
+        KeyCode::Char('k') => state.move_selection(-1, config),
+        KeyCode::Char('g') if pending_g => state.selected = 0,
+        KeyCode::Char('g') => state.pending_g = true,
+        KeyCode::Char('G') => state.select_last(config),
+        KeyCode::Char('b') => {
+            let Some(entry) = state.selected_entry(config) else {
+                return Ok(None);
+            };
+            let item = entry.item.clone();
+            if entry.section == Section::Sessions {
+                state.message = Some("Sessions can't be bookmarked".to_owned());
+            } else if state.app.bookmarks.iter().any(|x| x.path == item.path) {
+                state.message = Some(format!("{} is already bookmarked", item.path));
+            } else if let Some(max) = config
+                .max_bookmarks
+                .filter(|&x| state.app.bookmarks.len() >= x)
+            {
+                state.message = Some(format!("Bookmarks are full ({max} max)"));
+            } else {
+                let path = item.path.clone();
+                state.update(db_path, config, |app| {
+                    if app.bookmarks.iter().all(|x| x.path != item.path) {
+                        app.bookmarks.push(item);
+                    }
+                    Ok(())
+                })?;
+                state.message = Some(format!("Bookmarked {path}"));
+            }
+        }
+        KeyCode::Char('d') => {
+            if let Some(entry) = state.selected_entry(config) {
+                let key = entry.section.key(entry.item).to_owned();
+                state.prompt = Some(Prompt::Delete(entry.section.stored_in(), key));
+            }
+        }
+        KeyCode::Char('r') => {
+            if let Some(entry) = state.selected_entry(config) {
+                if entry.section == Section::Sessions {
+                    state.message = Some("Sessions are named when saved".to_owned());
+                    return Ok(None);
+                }
+                let label = entry.item.label.clone().unwrap_or_default();
+                state.prompt = Some(Prompt::Label(entry.item.path.clone(), label));
+            }
+        }
+        KeyCode::Char('p') => state.show_preview = !state.show_preview,
+        KeyCode::Char('P') => {
+            let count = state
+                .app
+                .paths()
+                .filter(|x| state.missing.contains(*x))
+                .count();
+            if count == 0 {
+                state.message = Some("No missing entries".to_owned());
+            } else {
+                state.prompt = Some(Prompt::Prune(count));
+            }
+        }
+        KeyCode::Char(c @ ('J' | 'K')) => {
+            let Some(entry) = state.selected_entry(config) else {
+                return Ok(None);
+            };
+            if entry.section != Section::Bookmarks {
+                return Ok(None);
+            }
+            let path = entry.item.path.clone();
+            state.update(db_path, config, |app| {
+                let Some(pos) = app.bookmarks.iter().position(|x| x.path == path) else {
+                    return Ok(());
+                };
+                let other = if c == 'J' {
+                    pos + 1
+                } else {
+                    pos.wrapping_sub(1)
+                };
+                if other < app.bookmarks.len() {
+                    app.bookmarks.swap(pos, other);
+                }
+                Ok(())
+            })?;
+            state.select_path(Section::Bookmarks, &path, config);
+        }
+        KeyCode::Char(c) if config.is_key(c) => {
+            pending_label.push(c);
+            let count = state.labeled(config);
+            if pending_label.chars().count() < config.label_len(count) {
+                state.pending_label = pending_label;
+            } else if let Some((section, item)) = config
+                .label_index(&pending_label, count)
+                .and_then(|x| state.entry(x, config))
+            {
+                return Ok(Some(Action::open(section, item)));
+            }
+        }
+        _ => {}
+    }
+    Ok(None)
+}
 
-    let mut lines = vec![
-        Line::styled("Recents", Style::default().fg(Color::Red)),
-        Line::default(),
+/// Removes the single entry matching `target`, which is either a jump label as
+/// shown on the start screen opened in `scope`, a path or a label. A prefix
+/// like `bookmarks:` only matches the entries of that section.
+fn delete(
+    app: &mut App,
+    target: &str,
+    config: &Config,
+    scope: Option<&Path>,
+) -> Result<(Section, Item)> {
+    let stored = [
+        Section::Recents,
+        Section::Projects,
+        Section::Sessions,
+        Section::Bookmarks,
     ];
-    for (i, item) in app.recents.iter().enumerate() {
-        lines.push(item.as_line(char::from_digit(i as u32, 16).unwrap()));
+    let prefixed = target.split_once(':').and_then(|(name, rest)| {
+        let section = stored.into_iter().find(|x| x.name() == name)?;
+        Some((section, rest))
+    });
+    let (only, target) = match prefixed {
+        Some((section, rest)) => (Some(section), rest),
+        None => (None, target),
+    };
+
+    // rank a copy, the stored order decides which recents the history keeps
+    let mut ranked = app.clone();
+    ranked.rank_recents(config.sort);
+    let listed: Vec<(Section, String)> = ranked
+        .listed(config, scope)
+        .into_iter()
+        .flat_map(|(section, items)| {
+            items
+                .into_iter()
+                .map(move |x| (section.stored_in(), section.key(x).to_owned()))
+        })
+        .collect();
+    let mut matches = Vec::new();
+    let jump = config.label_index(target, listed.len());
+    if let Some(idx) = jump {
+        matches.push(listed[idx].clone());
     }
-    lines.append(&mut vec![
-        Line::default(),
-        Line::styled("Bookmarks", Style::default().fg(Color::Red)),
-        Line::default(),
-    ]);
-    for (i, item) in app.bookmarks.iter().enumerate() {
-        lines.push(item.as_line(char::from_digit((i + app.recents.len()) as u32, 16).unwrap()));
+    let path = db::canonicalize(Path::new(target), config.resolve_symlinks)?;
+    let recents = app.recents.iter().map(|x| (Section::Recents, x));
+    let projects = app.projects.iter().map(|x| (Section::Projects, x));
+    let sessions = app.sessions.iter().map(|x| (Section::Sessions, &x.item));
+    let bookmarks = app.bookmarks.iter().map(|x| (Section::Bookmarks, x));
+    // sessions go by name only, their path is just where they were saved
+    let found = recents
+        .chain(projects)
+        .chain(sessions)
+        .chain(bookmarks)
+        .filter(|(section, x)| {
+            (*section != Section::Sessions && x.path == path) || x.label.as_deref() == Some(target)
+        })
+        .map(|(section, x)| (section, section.key(x).to_owned()));
+    for found in found {
+        if !matches.contains(&found) {
+            matches.push(found);
+        }
     }
+    matches.retain(|(section, _)| only.is_none_or(|x| x == *section));
 
-    let lines_width = lines.iter().map(|x| x.width()).max().unwrap();
-    let left_pad = (chunks[1].width - lines_width as u16) / 2;
+    let (section, key) = match <[_; 1]>::try_from(matches) {
+        Ok([found]) => found,
+        Err(matches) if matches.is_empty() => {
+            bail!("no entry matches the jump label, path or label {target:?}")
+        }
+        Err(matches) => {
+            let entries: Vec<String> = matches
+                .iter()
+                .map(|(section, key)| format!("{key} in {}", section.name()))
+                .collect();
+            let hint = match jump {
+                Some(_) => format!("write a path as `./{target}`"),
+                None => format!("prefix it with a section like `bookmarks:{target}`"),
+            };
+            bail!("{target:?} matches {}, {hint}", entries.join(" and "))
+        }
+    };
+    let item = app.remove(section, &key).unwrap();
+    Ok((section, item))
+}
 
-    f.render_widget(
-        Paragraph::new(lines).block(Block::default().padding(Padding::new(left_pad, 0, 5, 0))),
-        chunks[1],
-    );
+/// Records an open of the session `name` and replaces the process with the
+/// editor opening its files from its working directory, passing `flags` on.
+fn open_session(name: &str, flags: &[String], config: &Config, db_path: &Path) -> Result<()> {
+    let session = App::update(db_path, |app| {
+        let session = app
+            .open_session(name)
+            .with_context(|| format!("no session named {name:?}"))?;
+        for file in &session.files {
+            app.open(&file.path, file.line, file.column, config.history_size);
+        }
+        Ok(session)
+    })?;
+    env::set_current_dir(&session.item.path)
+        .with_context(|| format!("failed to enter {}", session.item.path))?;
+    let targets: Vec<Target> = session.files.iter().map(Target::from).collect();
+    Err(editor::exec(config.editor.as_deref(), flags, &targets))
+}
+
+/// Separates the paths among Helix' arguments from its flags and their values.
+fn split_args(args: Vec<String>) -> (Vec<String>, Vec<Target>) {
+    let (mut flags, mut targets) = (Vec::new(), Vec::new());
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            flags.push(arg);
+            targets.extend(args.by_ref().map(Target::parse));
+        } else if HELIX_VALUE_FLAGS.contains(&arg.as_str()) {
+            flags.push(arg);
+            flags.extend(args.next());
+        } else if HELIX_OPTIONAL_VALUE_FLAGS.contains(&arg.as_str()) {
+            flags.push(arg);
+            flags.extend(args.next_if(|x| !x.starts_with('-')));
+        } else if arg.starts_with('-') {
+            flags.push(arg);
+        } else {
+            targets.push(Target::parse(arg));
+        }
+    }
+    (flags, targets)
+}
+
+/// Resolves the database file from `--startify-db`/`$HELIX_STARTIFY_DB`, then
+/// `$XDG_DATA_HOME` and finally `$HOME/.local/share`.
+fn db_path(matches: &ArgMatches) -> Result<PathBuf> {
+    if let Some(path) = matches.get_one::<PathBuf>("startify-db") {
+        return Ok(path.clone());
+    }
+    let dir = config::xdg_dir("XDG_DATA_HOME", ".local/share").context(
+        "could not determine the database location, set HOME, XDG_DATA_HOME or HELIX_STARTIFY_DB",
+    )?;
+    Ok(dir.join("helix-startify/app.db"))
 }
 
 fn main() -> Result<()> {
     let matches = command!()
-        .arg(arg!([PATH] "File to open"))
-        .arg(arg!(-b --bookmark <PATH> "Add path to bookmarks"))
-        .arg(arg!(-d --delete <KEY> "Delete item from recents/bookmarks"))
+        .disable_help_flag(true)
+        .disable_version_flag(true)
+        .arg(arg!(--"startify-help" "Print help").action(ArgAction::Help))
+        .arg(arg!(--"startify-version" "Print version").action(ArgAction::Version))
+        .arg(arg!(--"startify-bookmark" <PATH> "Add path to bookmarks"))
+        .arg(arg!(--"startify-delete" <TARGET> "Delete an entry by jump label, path or label, optionally prefixed with its section like bookmarks:"))
+        .arg(arg!(--"startify-prune" "Remove entries of paths that no longer exist"))
+        .arg(arg!(--"startify-session-save" <NAME> "Save the files passed as a session"))
+        .arg(arg!(--"startify-session-open" <NAME> "Open all files of a session"))
+        .arg(
+            arg!(--"startify-db" <PATH> "Database file to use")
+                .env("HELIX_STARTIFY_DB")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!([ARGS] ... "Files and flags passed to Helix, the start screen is shown without any")
+                .trailing_var_arg(true)
+                .allow_hyphen_values(true),
+        )
+        .after_help("Flags of helix-startify must come before the ones passed to Helix.")
         .get_matches();
 
-    let db_path = format!(
-        "/home/{}/.local/share/helix-startify",
-        env::var("USER").unwrap()
-    );
+    let config = Config::load()?;
 
-    let _ = fs::create_dir(&db_path);
-    let _ = File::options()
-        .create_new(true)
-        .open(format!("{db_path}/app.db"));
+    let db_path = db_path(&matches)?;
 
-    let mut app: App =
-        serde_json::from_str(&fs::read_to_string(format!("{db_path}/app.db"))?).unwrap_or_default();
+    if let Some(path) = matches.get_one::<String>("startify-bookmark") {
+        let path = db::canonicalize(Path::new(path), config.resolve_symlinks)?;
+        return App::update(&db_path, |app| {
+            if app.bookmarks.iter().any(|x| x.path == path) {
+                bail!("{path} is already bookmarked");
+            }
+            if let Some(max) = config.max_bookmarks.filter(|&x| app.bookmarks.len() >= x) {
+                bail!("bookmarks are full ({max} max)");
+            }
+            app.bookmarks.push(Item::new(path.clone()));
+            Ok(())
+        });
+    }
 
-    if let Some(path) = matches.get_one::<String>("bookmark") {
-        if app.bookmarks.len() < 6 {
-            app.bookmarks.push(Item(path.clone()));
-            app.save(&db_path)?;
-        }
+    if matches.get_flag("startify-prune") {
+        let count = App::update(&db_path, |app| Ok(app.prune()))?;
+        println!("Removed {}", ui::missing_entries(count));
         return Ok(());
     }
 
-    if let Some(key) = matches.get_one::<String>("delete") {
-        let c = key.chars().next().unwrap();
-        let idx = c.to_digit(16).unwrap() as usize;
-        app.recents.remove(idx);
-        let idx = idx - app.recents.len();
-        app.bookmarks.remove(idx);
-        app.save(&db_path)?;
+    let scope = env::current_dir().ok().and_then(|x| project::scope(&x));
+
+    if let Some(key) = matches.get_one::<String>("startify-delete") {
+        let (section, item) =
+            App::update(&db_path, |app| delete(app, key, &config, scope.as_deref()))?;
+        println!("Deleted {} from {}", section.key(&item), section.name());
         return Ok(());
     }
 
-    if let Some(path) = matches.get_one::<String>("PATH") {
-        let path = env::current_dir().unwrap().join(path);
-        let item = Item(path.to_str().unwrap().to_owned());
-        if let Some(pos) = app.recents.iter().position(|x| x.eq(&item)) {
-            app.recents.remove(pos);
+    let args: Vec<String> = matches
+        .get_many::<String>("ARGS")
+        .map(|x| x.cloned().collect())
+        .unwrap_or_default();
+    if let Some(arg) = args.iter().find(|x| x.starts_with("--startify-")) {
+        bail!("{arg} must come before the arguments passed to Helix");
+    }
+
+    if let Some(name) = matches.get_one::<String>("startify-session-save") {
+        let (flags, targets) = split_args(args);
+        if name.trim().is_empty() {
+            bail!("the session name must not be empty");
+        }
+        if !flags.is_empty() {
+            bail!("sessions only store files, not {}", flags.join(" "));
         }
-        app.recents.push_front(item);
-        if app.recents.len() > 10 {
-            app.recents.pop_back();
+        if targets.is_empty() {
+            bail!("no files given for session {name:?}");
+        }
+        let dir = db::canonicalize(Path::new("."), false)?;
+        let files = targets
+            .iter()
+            .map(|x| {
+                let mut item = Item::new(db::canonicalize(
+                    Path::new(&x.path),
+                    config.resolve_symlinks,
+                )?);
+                (item.line, item.column) = (x.line, x.column);
+                Ok(item)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let count = files.len();
+        App::update(&db_path, |app| {
+            app.save_session(name, &dir, files);
+            Ok(())
+        })?;
+        println!("Saved session {name} with {} in {dir}", ui::files(count));
+        return Ok(());
+    }
+
+    if let Some(name) = matches.get_one::<String>("startify-session-open") {
+        let (flags, targets) = split_args(args);
+        if !targets.is_empty() {
+            bail!("--startify-session-open only takes flags for Helix");
+        }
+        return open_session(name, &flags, &config, &db_path);
+    }
+
+    if !args.is_empty() {
+        let (flags, targets) = split_args(args);
+        if !targets.is_empty() {
+            let paths = targets
+                .iter()
+                .map(|x| db::canonicalize(Path::new(&x.path), config.resolve_symlinks))
+                .collect::<Result<Vec<_>>>()?;
+            App::update(&db_path, |app| {
+                for (target, path) in targets.iter().zip(&paths) {
+                    app.open(path, target.line, target.column, config.history_size);
+                }
+                Ok(())
+            })?;
         }
-        app.save(&db_path)?;
-        Command::new("hx").arg(path).exec();
+        return Err(editor::exec(config.editor.as_deref(), &flags, &targets));
     }
 
+    let mut app = App::load(&db_path)?;
+    app.rank_recents(config.sort);
+    let header = Header::load(&config.header)?;
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -189,19 +615,138 @@ fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     let tick_rate = Duration::from_millis(250);
-    let res = run_app(&mut terminal, app, tick_rate)?;
+    let res = run_app(
+        &mut terminal,
+        State::new(app, header, scope, &config),
+        &config,
+        &db_path,
+        tick_rate,
+    );
+
+    // restore the terminal first, so errors are readable and the editor
+    // starts from a clean state
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+
+    match res? {
+        Some(Action::OpenSession(name)) => open_session(&name, &[], &config, &db_path),
+        Some(Action::Open(section, target)) => open(section, target, &config, &db_path),
+        _ => Ok(()),
+    }
+}
 
-    if let Some(path) = res {
-        Command::new("hx").arg(path).exec();
-    } else {
-        disable_raw_mode()?;
-        execute!(
-            terminal.backend_mut(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
-        )?;
-        terminal.show_cursor()?;
+/// Records an open of `target` listed in `section` and replaces the process
+/// with the editor opening it.
+fn open(section: Section, target: Target, config: &Config, db_path: &Path) -> Result<()> {
+    if section == Section::Projects {
+        // Helix opens its file picker on a directory, relative to the root
+        App::update(db_path, |app| {
+            app.open_project(&target.path, config.history_size);
+            Ok(())
+        })?;
+        env::set_current_dir(&target.path)
+            .with_context(|| format!("failed to enter {}", target.path))?;
+        let root = Target::new(".".to_owned());
+        return Err(editor::exec(config.editor.as_deref(), &[], &[root]));
     }
+    App::update(db_path, |app| {
+        app.open(&target.path, None, None, config.history_size);
+        Ok(())
+    })?;
+    Err(editor::exec(config.editor.as_deref(), &[], &[target]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
+    fn app(recents: &[&str], bookmarks: &[&str]) -> App {
+        let mut app = App::default();
+        app.recents
+            .extend(recents.iter().map(|x| Item::new(x.to_string())));
+        app.bookmarks
+            .extend(bookmarks.iter().map(|x| Item::new(x.to_string())));
+        app
+    }
+
+    fn split(args: &[&str]) -> (Vec<String>, Vec<String>) {
+        let (flags, targets) = split_args(args.iter().map(|x| x.to_string()).collect());
+        (flags, targets.into_iter().map(|x| x.path).collect())
+    }
+
+    #[test]
+    fn split_health() {
+        assert_eq!(split(&["--health"]), (vec!["--health".into()], vec![]));
+        assert_eq!(
+            split(&["--health", "rust"]),
+            (vec!["--health".into(), "rust".into()], vec![])
+        );
+        assert_eq!(
+            split(&["--health", "-v", "a.rs"]),
+            (vec!["--health".into(), "-v".into()], vec!["a.rs".into()])
+        );
+    }
+
+    #[test]
+    fn split_value_flags() {
+        assert_eq!(
+            split(&["-c", "my.toml", "a.rs"]),
+            (vec!["-c".into(), "my.toml".into()], vec!["a.rs".into()])
+        );
+        assert_eq!(
+            split(&["--vsplit", "a.rs", "b.rs"]),
+            (vec!["--vsplit".into()], vec!["a.rs".into(), "b.rs".into()])
+        );
+    }
+
+    #[test]
+    fn split_double_dash() {
+        assert_eq!(
+            split(&["-v", "--", "-a.rs", "--health"]),
+            (
+                vec!["-v".into(), "--".into()],
+                vec!["-a.rs".into(), "--health".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn delete_ambiguous_jump_label() {
+        let config = Config::default();
+        let cwd = env::current_dir().unwrap();
+        let c = cwd.join("c").to_string_lossy().into_owned();
+        let mut recents: Vec<String> = (0..12).map(|x| format!("/r{x}")).collect();
+        recents.push(c.clone());
+        let recents: Vec<&str> = recents.iter().map(String::as_str).collect();
+        // 10 recents and 2 bookmarks are listed, `c` labels the second bookmark
+        let mut app = app(&recents, &["/b0", "/b1"]);
+
+        assert!(delete(&mut app, "c", &config, None).is_err());
+        assert_eq!(app.recents.len(), 13);
+        assert_eq!(app.bookmarks.len(), 2);
+
+        let (section, item) = delete(&mut app, "./c", &config, None).unwrap();
+        assert!(section == Section::Recents);
+        assert_eq!(item.path, c);
+        let (section, item) = delete(&mut app, "c", &config, None).unwrap();
+        assert!(section == Section::Bookmarks);
+        assert_eq!(item.path, "/b1");
+    }
+
+    #[test]
+    fn delete_by_section() {
+        let config = Config::default();
+        let mut app = app(&["/a.rs"], &["/a.rs"]);
+
+        assert!(delete(&mut app, "/a.rs", &config, None).is_err());
+        let (section, _) = delete(&mut app, "bookmarks:/a.rs", &config, None).unwrap();
+        assert!(section == Section::Bookmarks);
+        assert_eq!(app.recents.len(), 1);
+        assert!(app.bookmarks.is_empty());
+    }
 }
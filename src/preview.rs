@@ -0,0 +1,255 @@
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+use ratatui::prelude::*;
+
+use crate::config::Colors;
+
+/// Bytes of a file read for its preview, plenty for a screen of lines.
+const MAX_BYTES: u64 = 64 * 1024;
+/// Lines of a file or entries of a directory previewed.
+const MAX_LINES: usize = 200;
+/// Entries of a directory read to pick the first ones to preview.
+const MAX_ENTRIES: usize = 10_000;
+const TAB_WIDTH: usize = 4;
+
+/// How the files of a language are highlighted.
+struct Syntax {
+    /// Starts a comment running to the end of the line, empty if there is none.
+    comment: &'static str,
+    quotes: &'static [char],
+    keywords: &'static [&'static str],
+}
+
+const RUST: Syntax = Syntax {
+    comment: "//",
+    // single quotes also start lifetimes
+    quotes: &['"'],
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ],
+};
+
+const C_LIKE: Syntax = Syntax {
+    comment: "//",
+    quotes: &['"', '\'', '`'],
+    keywords: &[
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "do",
+        "else",
+        "enum",
+        "export",
+        "extends",
+        "false",
+        "final",
+        "finally",
+        "for",
+        "func",
+        "function",
+        "go",
+        "if",
+        "import",
+        "interface",
+        "let",
+        "new",
+        "nil",
+        "null",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "static",
+        "struct",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "type",
+        "typedef",
+        "var",
+        "void",
+        "while",
+    ],
+};
+
+const PYTHON: Syntax = Syntax {
+    comment: "#",
+    quotes: &['"', '\''],
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "False", "finally", "for", "from", "if", "import", "in", "is",
+        "lambda", "None", "not", "or", "pass", "raise", "return", "True", "try", "while", "with",
+        "yield",
+    ],
+};
+
+const SHELL: Syntax = Syntax {
+    comment: "#",
+    quotes: &['"', '\''],
+    keywords: &[
+        "case", "do", "done", "elif", "else", "end", "esac", "export", "fi", "for", "function",
+        "if", "in", "local", "return", "set", "then", "while",
+    ],
+};
+
+const DATA: Syntax = Syntax {
+    comment: "#",
+    quotes: &['"', '\''],
+    keywords: &["false", "null", "true"],
+};
+
+const JSON: Syntax = Syntax {
+    comment: "",
+    quotes: &['"'],
+    keywords: &["false", "null", "true"],
+};
+
+fn syntax(path: &Path) -> Option<&'static Syntax> {
+    Some(match path.extension()?.to_str()? {
+        "rs" => &RUST,
+        "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "go" | "java" | "js" | "jsx" | "kt" | "swift"
+        | "ts" | "tsx" | "zig" => &C_LIKE,
+        "py" => &PYTHON,
+        "sh" | "bash" | "zsh" | "fish" => &SHELL,
+        "toml" | "yaml" | "yml" | "ini" | "conf" => &DATA,
+        "json" => &JSON,
+        _ => return None,
+    })
+}
+
+/// Returns the lines previewing `path`: the start of a file, highlighted if
+/// its language is known, or the entries of a directory.
+pub fn preview(path: &Path, colors: &Colors) -> Vec<Line<'static>> {
+    let note = |x: String| vec![Line::styled(x, Style::default().fg(colors.path))];
+    let res = fs::metadata(path).and_then(|x| {
+        if x.is_dir() {
+            directory(path, colors)
+        } else if x.is_file() {
+            file(path, colors)
+        } else {
+            // reading pipes or devices could block
+            Ok(note("Not a regular file".to_owned()))
+        }
+    });
+    match res {
+        Ok(lines) => lines,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => note("No longer exists".to_owned()),
+        Err(e) => note(e.to_string()),
+    }
+}
+
+/// Lists the first entries of `path`, directories first. Huge directories
+/// are only partly read, which the listing starts with a note about.
+fn directory(path: &Path, colors: &Colors) -> io::Result<Vec<Line<'static>>> {
+    let mut entries: Vec<(bool, String)> = fs::read_dir(path)?
+        .filter_map(Result::ok)
+        .take(MAX_ENTRIES)
+        .map(|x| {
+            let name = x.file_name().to_string_lossy().into_owned();
+            (!x.file_type().is_ok_and(|x| x.is_dir()), name)
+        })
+        .collect();
+    let partial = entries.len() == MAX_ENTRIES;
+    entries.sort();
+    let mut lines: Vec<Line<'static>> = entries
+        .into_iter()
+        .take(MAX_LINES)
+        .map(|(is_file, name)| {
+            if is_file {
+                Line::styled(name, Style::default().fg(colors.name))
+            } else {
+                Line::styled(format!("{name}/"), Style::default().fg(colors.key))
+            }
+        })
+        .collect();
+    if partial {
+        let note = format!("Sorted from the first {MAX_ENTRIES} entries only");
+        lines.insert(0, Line::styled(note, Style::default().fg(colors.path)));
+    }
+    Ok(lines)
+}
+
+fn file(path: &Path, colors: &Colors) -> io::Result<Vec<Line<'static>>> {
+    let mut bytes = Vec::new();
+    File::open(path)?.take(MAX_BYTES).read_to_end(&mut bytes)?;
+    if bytes.contains(&0) {
+        let style = Style::default().fg(colors.path);
+        return Ok(vec![Line::styled("Binary file", style)]);
+    }
+    let syntax = syntax(path);
+    Ok(String::from_utf8_lossy(&bytes)
+        .lines()
+        .take(MAX_LINES)
+        .map(|x| {
+            let line = x.replace('\t', &" ".repeat(TAB_WIDTH));
+            match syntax {
+                Some(syntax) => highlight(&line, syntax, colors),
+                None => Line::styled(line, Style::default().fg(colors.name)),
+            }
+        })
+        .collect())
+}
+
+/// Colors the comments, strings, numbers and keywords of a single line, a
+/// comment or string spanning lines is only colored on its first line.
+fn highlight(line: &str, syntax: &Syntax, colors: &Colors) -> Line<'static> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        let (len, color) = if !syntax.comment.is_empty() && rest.starts_with(syntax.comment) {
+            (rest.len(), colors.comment)
+        } else if syntax.quotes.contains(&c) {
+            (string_len(rest, c), colors.string)
+        } else if c.is_ascii_digit() {
+            (word_len(rest), colors.number)
+        } else if c.is_alphabetic() || c == '_' {
+            let len = word_len(rest);
+            if syntax.keywords.contains(&&rest[..len]) {
+                (len, colors.keyword)
+            } else {
+                (len, colors.name)
+            }
+        } else {
+            (c.len_utf8(), colors.name)
+        };
+        let (text, tail) = rest.split_at(len);
+        match spans.last_mut() {
+            Some(last) if last.style.fg == Some(color) => last.content.to_mut().push_str(text),
+            _ => spans.push(Span::styled(text.to_owned(), Style::default().fg(color))),
+        }
+        rest = tail;
+    }
+    Line::from(spans)
+}
+
+fn word_len(s: &str) -> usize {
+    s.find(|x: char| !x.is_alphanumeric() && x != '_')
+        .unwrap_or(s.len())
+}
+
+/// Returns the length of the string `s` starts with, up to the closing
+/// `quote` or the end of the line.
+fn string_len(s: &str, quote: char) -> usize {
+    let mut chars = s.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == quote {
+            return i + c.len_utf8();
+        }
+    }
+    s.len()
+}
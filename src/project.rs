@@ -0,0 +1,35 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Returns the root of the git repository containing `path`, the closest
+/// directory with a `.git` (a file in worktrees).
+pub fn git_root(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|x| x.join(".git").exists())
+}
+
+/// Returns the directory whose recents get their own section: the repository
+/// `cwd` is in, or `cwd` itself. `None` for `$HOME` and `/`, where the section
+/// would only repeat the recents.
+pub fn scope(cwd: &Path) -> Option<PathBuf> {
+    let dir = git_root(cwd).unwrap_or(cwd);
+    let home = env::var_os("HOME").map(PathBuf::from);
+    (dir.parent().is_some() && Some(dir) != home.as_deref()).then(|| dir.to_owned())
+}
+
+/// Returns the checked out branch of the repository at `root`, or the short
+/// commit hash when the head is detached.
+pub fn branch(root: &Path) -> Option<String> {
+    let git = root.join(".git");
+    // worktrees and submodules have a file pointing at the git directory
+    let dir = match fs::read_to_string(&git) {
+        Ok(link) => root.join(link.strip_prefix("gitdir:")?.trim()),
+        Err(_) => git,
+    };
+    let head = fs::read_to_string(dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref: ") {
+        Some(name) => Some(name.strip_prefix("refs/heads/").unwrap_or(name).to_owned()),
+        None => Some(head.get(..7)?.to_owned()),
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use ratatui::style::Color;
+use toml::{Table, Value};
+
+use crate::config::{self, Colors};
+use crate::editor::which;
+
+/// How deep `inherits` chains are followed, guarding against cycles.
+const MAX_INHERITS: usize = 16;
+
+/// A Helix theme, flattened to its scopes with `inherits` resolved.
+pub struct Theme {
+    styles: HashMap<String, Value>,
+    palette: HashMap<String, String>,
+}
+
+impl Theme {
+    /// Loads the Helix theme `name`, `None` if it cannot be found or parsed.
+    /// Helix' built-in `default` theme has no file and is never found.
+    pub fn load(name: &str) -> Option<Self> {
+        Self::load_depth(name, 0)
+    }
+
+    /// Loads the theme set in `$XDG_CONFIG_HOME/helix/config.toml`.
+    pub fn load_helix() -> Option<Self> {
+        let path = helix_dir()?.join("config.toml");
+        let config: Table = toml::from_str(&fs::read_to_string(path).ok()?).ok()?;
+        Self::load(config.get("theme")?.as_str()?)
+    }
+
+    fn load_depth(name: &str, depth: usize) -> Option<Self> {
+        if depth > MAX_INHERITS || name.contains('/') {
+            return None;
+        }
+        let table: Table = search_dirs()
+            .into_iter()
+            .find_map(|x| fs::read_to_string(x.join(format!("{name}.toml"))).ok())
+            .and_then(|x| toml::from_str(&x).ok())?;
+
+        let mut theme = match table.get("inherits").and_then(Value::as_str) {
+            Some(parent) => Self::load_depth(parent, depth + 1)?,
+            None => Self {
+                styles: HashMap::new(),
+                palette: HashMap::new(),
+            },
+        };
+        for (key, value) in table {
+            match (key.as_str(), value) {
+                ("inherits", _) => {}
+                ("palette", Value::Table(palette)) => {
+                    for (name, color) in palette {
+                        if let Value::String(color) = color {
+                            theme.palette.insert(name, color);
+                        }
+                    }
+                }
+                (_, value) => flatten(&mut theme.styles, key, value),
+            }
+        }
+        Some(theme)
+    }
+
+    /// Returns the foreground of `scope`, falling back to its parent scopes
+    /// like Helix does.
+    pub fn fg(&self, scope: &str) -> Option<Color> {
+        match self.style(scope)? {
+            Value::String(color) => self.color(color),
+            Value::Table(style) => self.color(style.get("fg")?.as_str()?),
+            _ => None,
+        }
+    }
+
+    pub fn bg(&self, scope: &str) -> Option<Color> {
+        match self.style(scope)? {
+            Value::Table(style) => self.color(style.get("bg")?.as_str()?),
+            _ => None,
+        }
+    }
+
+    fn style(&self, scope: &str) -> Option<&Value> {
+        std::iter::successors(Some(scope), |x| Some(x.rsplit_once('.')?.0))
+            .find_map(|x| self.styles.get(x))
+    }
+
+    fn color(&self, name: &str) -> Option<Color> {
+        match self.palette.get(name).map_or(name, String::as_str) {
+            // Helix' gray is the bright black of the terminal palette
+            "gray" => Some(Color::DarkGray),
+            "light-gray" => Some(Color::Gray),
+            "default" => Some(Color::Reset),
+            name => Color::from_str(name).ok(),
+        }
+    }
+
+    /// Colors of the start screen picked from the scopes Helix uses for the
+    /// equivalent parts of the editor, `fallback` for the ones not set.
+    pub fn colors(&self, fallback: Colors) -> Colors {
+        Colors {
+            logo: self.fg("keyword").unwrap_or(fallback.logo),
+            header: self.fg("keyword").unwrap_or(fallback.header),
+            bracket: self.fg("ui.linenr").unwrap_or(fallback.bracket),
+            key: self.fg("constant.numeric").unwrap_or(fallback.key),
+            path: self.fg("comment").unwrap_or(fallback.path),
+            name: self.fg("ui.text").unwrap_or(fallback.name),
+            matched: self.fg("special").unwrap_or(fallback.matched),
+            selected: self
+                .bg("ui.menu.selected")
+                .or_else(|| self.bg("ui.selection"))
+                .unwrap_or(fallback.selected),
+            hovered: self.bg("ui.cursorline.primary").unwrap_or(fallback.hovered),
+            background: self.bg("ui.background").unwrap_or(fallback.background),
+            keyword: self.fg("keyword").unwrap_or(fallback.keyword),
+            string: self.fg("string").unwrap_or(fallback.string),
+            number: self.fg("constant.numeric").unwrap_or(fallback.number),
+            comment: self.fg("comment").unwrap_or(fallback.comment),
+        }
+    }
+}
+
+/// Collects the styles of `value` under `key`, which may be written as nested
+/// tables (`[ui.text]`) instead of a quoted dotted key.
+fn flatten(styles: &mut HashMap<String, Value>, key: String, value: Value) {
+    match value {
+        Value::Table(table) if !is_style(&table) => {
+            for (child, value) in table {
+                flatten(styles, format!("{key}.{child}"), value);
+            }
+        }
+        value => {
+            styles.insert(key, value);
+        }
+    }
+}
+
+fn is_style(table: &Table) -> bool {
+    ["fg", "bg", "modifiers", "underline"]
+        .iter()
+        .any(|x| table.contains_key(*x))
+}
+
+fn helix_dir() -> Option<PathBuf> {
+    config::xdg_dir("XDG_CONFIG_HOME", ".config").map(|x| x.join("helix"))
+}
+
+/// Directories searched for `<theme>.toml`, in the order Helix searches them.
+fn search_dirs() -> Vec<PathBuf> {
+    let mut runtimes = Vec::new();
+    if let Some(dir) = helix_dir() {
+        runtimes.push(dir.join("runtime"));
+    }
+    if let Some(dir) = env::var_os("HELIX_RUNTIME") {
+        runtimes.push(PathBuf::from(dir));
+    }
+    // runtime shipped next to the executable, as release archives do
+    if let Some(dir) = which("hx")
+        .and_then(|x| fs::canonicalize(x).ok())
+        .and_then(|x| x.parent().map(|x| x.join("runtime")))
+    {
+        runtimes.push(dir);
+    }
+    runtimes.extend(
+        [
+            "/usr/lib/helix/runtime",
+            "/usr/share/helix/runtime",
+            "/usr/local/lib/helix/runtime",
+            "/opt/homebrew/opt/helix/libexec/runtime",
+        ]
+        .map(PathBuf::from),
+    );
+    helix_dir()
+        .into_iter()
+        .chain(runtimes)
+        .map(|x| x.join("themes"))
+        .collect()
+}
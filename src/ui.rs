@@ -0,0 +1,928 @@
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use ratatui::{prelude::*, widgets::*};
+
+use crate::config::{Colors, Config, Parents, PathsConfig};
+use crate::db::{self, App, Item, Kind, Section};
+use crate::fuzzy::fuzzy_match;
+use crate::header::Header;
+use crate::preview;
+use crate::project;
+
+/// Narrowest terminal the preview is shown on, half of it is left for the
+/// list.
+const PREVIEW_MIN_WIDTH: u16 = 100;
+
+pub struct Entry<'a> {
+    pub item: &'a Item,
+    pub section: Section,
+    /// Char indices of `item.path` matched by the filter.
+    pub matches: Vec<usize>,
+}
+
+/// Input requested from the user at the bottom of the screen.
+pub enum Prompt {
+    /// Asks for confirmation before deleting the path from the section.
+    Delete(Section, String),
+    /// Edits the label of the path.
+    Label(String, String),
+    /// Asks for confirmation before removing the given number of entries of
+    /// missing paths.
+    Prune(usize),
+}
+
+pub struct State {
+    pub app: App,
+    pub header: Header,
+    /// Directory whose recents are listed in their own section.
+    pub scope: Option<PathBuf>,
+    /// Checked out branch of each listed project.
+    branches: HashMap<String, String>,
+    /// Receives the branches being read.
+    reading_branches: Option<Receiver<HashMap<String, String>>>,
+    /// Listed paths that no longer exist, as of the last check.
+    pub missing: HashSet<String>,
+    /// Receives the result of the running existence check.
+    checking: Option<Receiver<HashSet<String>>>,
+    /// Query of the fuzzy filter, `None` when not filtering.
+    pub filter: Option<String>,
+    /// Index of the highlighted entry in `entries`.
+    pub selected: usize,
+    /// Whether `g` was pressed, waiting for the second `g` of `gg`.
+    pub pending_g: bool,
+    /// Jump label typed so far.
+    pub pending_label: String,
+    /// Entry under the mouse pointer.
+    pub hovered: Option<usize>,
+    pub prompt: Option<Prompt>,
+    /// Shown at the bottom of the screen until the next key press.
+    pub message: Option<String>,
+    /// Whether the preview is shown when the terminal is wide enough.
+    pub show_preview: bool,
+    /// Preview of the last path read, read again once another is selected.
+    preview: Option<(String, Vec<Line<'static>>)>,
+    /// Receives the preview of the path being read.
+    previewing: Option<(String, Receiver<Vec<Line<'static>>>)>,
+    /// First list line shown, kept so the selection stays visible.
+    scroll: usize,
+    /// Half the height of the list in the last frame.
+    pub page: usize,
+    /// Screen area of each entry rendered in the last frame.
+    rows: Vec<(Rect, usize)>,
+}
+
+impl State {
+    pub fn new(app: App, header: Header, scope: Option<PathBuf>, config: &Config) -> Self {
+        Self {
+            branches: HashMap::new(),
+            reading_branches: Some(read_branches(&app, config)),
+            missing: HashSet::new(),
+            checking: Some(check_missing(&app)),
+            app,
+            header,
+            scope,
+            filter: None,
+            selected: 0,
+            pending_g: false,
+            pending_label: String::new(),
+            hovered: None,
+            prompt: None,
+            message: None,
+            show_preview: config.preview,
+            preview: None,
+            previewing: None,
+            scroll: 0,
+            page: 1,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Persists the changes made by `f` and reloads the database, keeping
+    /// changes made by other processes in the meantime.
+    pub fn update(
+        &mut self,
+        db_path: &Path,
+        config: &Config,
+        f: impl FnOnce(&mut App) -> Result<()>,
+    ) -> Result<()> {
+        App::update(db_path, f)?;
+        self.app = App::load(db_path)?;
+        self.app.rank_recents(config.sort);
+        self.reading_branches = Some(read_branches(&self.app, config));
+        self.checking = Some(check_missing(&self.app));
+        self.preview = None;
+        self.move_selection(0, config);
+        Ok(())
+    }
+
+    /// Returns the index of the entry rendered at the given screen position.
+    pub fn entry_at(&self, column: u16, row: u16) -> Option<usize> {
+        self.rows
+            .iter()
+            .find(|(rect, _)| rect.intersects(Rect::new(column, row, 1, 1)))
+            .map(|&(_, idx)| idx)
+    }
+
+    /// Takes the branches once they are read.
+    fn poll_branches(&mut self) {
+        let read = self
+            .reading_branches
+            .as_ref()
+            .and_then(|x| x.try_recv().ok());
+        if let Some(branches) = read {
+            self.branches = branches;
+            self.reading_branches = None;
+        }
+    }
+
+    /// Takes the result of the existence check once it is done.
+    fn poll_missing(&mut self) {
+        if let Some(missing) = self.checking.as_ref().and_then(|x| x.try_recv().ok()) {
+            self.missing = missing;
+            self.checking = None;
+        }
+    }
+
+    /// Greys out the line of `item` if its path no longer exists.
+    fn mark_missing(&self, line: &mut Line, item: &Item, config: &Config) {
+        if self.missing.contains(&item.path) {
+            line.patch_style(Style::default().fg(config.colors.path));
+        }
+    }
+
+    /// Returns the preview of the selected entry, `None` while it is read.
+    fn preview(&mut self, config: &Config) -> Option<&[Line<'static>]> {
+        if let Some((path, rx)) = &self.previewing {
+            if let Ok(lines) = rx.try_recv() {
+                self.preview = Some((path.clone(), lines));
+                self.previewing = None;
+            }
+        }
+        let path = self.selected_entry(config)?.item.path.clone();
+        if self.preview.as_ref().map(|x| &x.0) == Some(&path) {
+            return self.preview.as_ref().map(|x| x.1.as_slice());
+        }
+        if self.previewing.as_ref().map(|x| &x.0) != Some(&path) {
+            let rx = read_preview(path.clone(), config.colors);
+            self.previewing = Some((path, rx));
+        }
+        None
+    }
+
+    /// Returns the note shown next to `item`: whether it is missing, the
+    /// branch of a project or the number of files of a session.
+    fn note(&self, section: Section, item: &Item) -> Option<String> {
+        if self.missing.contains(&item.path) {
+            return Some("missing".to_owned());
+        }
+        match section {
+            Section::Projects => self.branches.get(&item.path).cloned(),
+            Section::Sessions => {
+                let name = section.key(item);
+                let session = self.app.sessions.iter().find(|x| x.name() == name)?;
+                Some(files(session.files.len()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the listed sections with their items, in display order.
+    pub fn sections(&self, config: &Config) -> Vec<(Section, Vec<&Item>)> {
+        self.app.listed(config, self.scope.as_deref())
+    }
+
+    /// Returns the number of entries that get a jump label.
+    pub fn labeled(&self, config: &Config) -> usize {
+        self.sections(config).iter().map(|x| x.1.len()).sum()
+    }
+
+    /// Returns the entry with the jump label at `idx`.
+    pub fn entry(&self, idx: usize, config: &Config) -> Option<(Section, &Item)> {
+        self.sections(config)
+            .into_iter()
+            .flat_map(|(section, items)| items.into_iter().map(move |x| (section, x)))
+            .nth(idx)
+    }
+
+    /// Returns the entries currently listed, in display order.
+    pub fn entries(&self, config: &Config) -> Vec<Entry<'_>> {
+        if self.filter.is_some() {
+            return self.filtered();
+        }
+        self.sections(config)
+            .into_iter()
+            .flat_map(|(section, items)| items.into_iter().map(move |item| (section, item)))
+            .map(|(section, item)| Entry {
+                item,
+                section,
+                matches: Vec::new(),
+            })
+            .collect()
+    }
+
+    pub fn selected_entry(&self, config: &Config) -> Option<Entry<'_>> {
+        self.entries(config).into_iter().nth(self.selected)
+    }
+
+    /// Moves the selection by `delta` entries, stopping at either end.
+    pub fn move_selection(&mut self, delta: isize, config: &Config) {
+        let len = self.entries(config).len();
+        self.selected = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+    }
+
+    pub fn select_last(&mut self, config: &Config) {
+        self.selected = self.entries(config).len().saturating_sub(1);
+    }
+
+    /// Selects the entry of `path` in `section`, if it is listed.
+    pub fn select_path(&mut self, section: Section, path: &str, config: &Config) {
+        if let Some(idx) = self
+            .entries(config)
+            .iter()
+            .position(|x| x.section == section && x.item.path == path)
+        {
+            self.selected = idx;
+        }
+    }
+
+    /// Returns all recents, projects and bookmarks matching the filter, best
+    /// match first.
+    pub fn filtered(&self) -> Vec<Entry<'_>> {
+        let query = self.filter.as_deref().unwrap_or_default();
+        let mut seen = std::collections::HashSet::new();
+        let recents = self.app.recents.iter().map(|x| (x, Section::Recents));
+        let projects = self.app.projects.iter().map(|x| (x, Section::Projects));
+        let bookmarks = self.app.bookmarks.iter().map(|x| (x, Section::Bookmarks));
+        let mut res: Vec<_> = recents
+            .chain(projects)
+            .chain(bookmarks)
+            .filter(|(x, _)| seen.insert(x.path.as_str()))
+            .filter_map(|(item, section)| {
+                let (score, matches) = fuzzy_match(query, &item.path)?;
+                Some((
+                    score,
+                    Entry {
+                        item,
+                        section,
+                        matches,
+                    },
+                ))
+            })
+            .collect();
+        res.sort_by_key(|x| std::cmp::Reverse(x.0));
+        res.into_iter().map(|x| x.1).collect()
+    }
+}
+
+/// Returns "1 file" or "`count` files".
+pub fn files(count: usize) -> String {
+    match count {
+        1 => "1 file".to_owned(),
+        n => format!("{n} files"),
+    }
+}
+
+/// Returns "1 missing entry" or "`count` missing entries".
+pub fn missing_entries(count: usize) -> String {
+    match count {
+        1 => "1 missing entry".to_owned(),
+        n => format!("{n} missing entries"),
+    }
+}
+
+/// Reads the preview of `path` on a separate thread, like `check_missing`
+/// reading must not block drawing.
+fn read_preview(path: String, colors: Colors) -> Receiver<Vec<Line<'static>>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(preview::preview(Path::new(&path), &colors));
+    });
+    rx
+}
+
+/// Checks which paths of `app` exist on a separate thread, as slow or hung
+/// file systems must not block drawing.
+fn check_missing(app: &App) -> Receiver<HashSet<String>> {
+    let paths: Vec<String> = app.paths().map(str::to_owned).collect();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let missing = paths
+            .into_iter()
+            .filter(|x| !Path::new(x).exists())
+            .collect();
+        let _ = tx.send(missing);
+    });
+    rx
+}
+
+/// Reads the branch of every listed project on a separate thread, kept until
+/// the database is reloaded.
+fn read_branches(app: &App, config: &Config) -> Receiver<HashMap<String, String>> {
+    let roots: Vec<String> = app
+        .projects
+        .iter()
+        .take(config.max_projects)
+        .map(|x| x.path.clone())
+        .collect();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let branches = roots
+            .into_iter()
+            .filter_map(|x| {
+                let branch = project::branch(Path::new(&x))?;
+                Some((x, branch))
+            })
+            .collect();
+        let _ = tx.send(branches);
+    });
+    rx
+}
+
+impl Item {
+    /// Renders the item in at most `width` columns, highlighting the chars of
+    /// its path at `matches`. `depth` limits the parent directory to its last
+    /// components. When space is short the open time is dropped first, then
+    /// the parent directory is shortened in the middle.
+    fn as_line(
+        &self,
+        label: Option<String>,
+        config: &Config,
+        matches: &[usize],
+        depth: Option<usize>,
+        note: Option<String>,
+        width: usize,
+    ) -> Line<'static> {
+        let colors = &config.colors;
+        // the parent keeps its trailing slash, the root directory is all name
+        let split = self
+            .path
+            .rfind('/')
+            .filter(|&x| x + 1 < self.path.len())
+            .map_or(0, |x| x + 1);
+        let (parent, name) = self.path.split_at(split);
+        let matched = Style::default()
+            .fg(colors.matched)
+            .add_modifier(Modifier::BOLD);
+        let mut spans = match label {
+            Some(label) => vec![
+                Span::styled("[", Style::default().fg(colors.bracket)),
+                Span::styled(label, Style::default().fg(colors.key)),
+                Span::styled("]  ", Style::default().fg(colors.bracket)),
+            ],
+            None => vec![Span::raw("     ")],
+        };
+        if let Some(label) = &self.label {
+            spans.push(Span::styled(
+                label.clone(),
+                Style::default().fg(colors.name),
+            ));
+            spans.push(Span::raw("  "));
+        }
+        let name_color = if self.label.is_some() {
+            colors.path
+        } else {
+            colors.name
+        };
+        let offset = parent.chars().count();
+        let name_chars = name
+            .chars()
+            .enumerate()
+            .map(|(i, c)| (c, matches.contains(&(i + offset))))
+            .collect();
+        let mut tail = highlight(
+            name_chars,
+            Style::default().fg(name_color),
+            matched,
+            usize::MAX,
+        );
+        if self.kind == Kind::Directory && !name.ends_with('/') {
+            tail.push(Span::styled("/", Style::default().fg(name_color)));
+        }
+        if let Some(line) = self.line {
+            let pos = match self.column {
+                Some(column) => format!(":{line}:{column}"),
+                None => format!(":{line}"),
+            };
+            tail.push(Span::styled(pos, Style::default().fg(colors.path)));
+        }
+        if let Some(note) = note {
+            tail.push(Span::styled(
+                format!("  {note}"),
+                Style::default().fg(colors.key),
+            ));
+        }
+
+        let parent: Vec<(char, bool)> = display_parent(parent, &config.paths, depth)
+            .into_iter()
+            .map(|(c, i)| (c, i.is_some_and(|i| matches.contains(&i))))
+            .collect();
+        let fixed: usize = spans.iter().chain(&tail).map(Span::width).sum();
+        let ago = Some(self.last_opened)
+            .filter(|&x| x > 0)
+            .map(|x| format!("  {}", ago(x)))
+            .filter(|x| fixed + parent.len() + x.chars().count() <= width);
+        let ago_width = ago.as_ref().map_or(0, |x| x.chars().count());
+        spans.extend(highlight(
+            parent,
+            Style::default().fg(colors.path),
+            matched,
+            width.saturating_sub(fixed + ago_width),
+        ));
+        spans.extend(tail);
+        if let Some(ago) = ago {
+            spans.push(Span::styled(ago, Style::default().fg(colors.path)));
+        }
+        Line::from(spans)
+    }
+}
+
+/// Returns the part of `path` after `$HOME`, if it is in there.
+fn strip_home(path: &str) -> Option<&str> {
+    let home = env::var("HOME").ok()?;
+    let home = home.trim_end_matches('/');
+    path.strip_prefix(home)
+        .filter(|x| !home.is_empty() && (x.is_empty() || x.starts_with('/')))
+}
+
+/// Returns `dir` with `$HOME` shown as `~` if configured.
+fn display_dir(dir: &Path, config: &Config) -> String {
+    let dir = dir.to_string_lossy();
+    match strip_home(&dir).filter(|_| config.paths.tilde) {
+        Some(rest) => format!("~{rest}"),
+        None => dir.into_owned(),
+    }
+}
+
+/// Returns the chars of `parent` as configured, each with its char index in
+/// the path, `None` for the ones standing in for others.
+fn display_parent(
+    parent: &str,
+    config: &PathsConfig,
+    depth: Option<usize>,
+) -> Vec<(char, Option<usize>)> {
+    let mut chars: Vec<_> = parent
+        .chars()
+        .enumerate()
+        .map(|(i, c)| (c, Some(i)))
+        .collect();
+    if let Some(rest) = strip_home(parent).filter(|_| config.tilde) {
+        let len = parent.chars().count() - rest.chars().count();
+        chars.splice(..len, [('~', None)]);
+    }
+
+    // components keep their trailing slash, `/` is one on its own
+    let mut components: Vec<Vec<_>> = chars
+        .split_inclusive(|x| x.0 == '/')
+        .map(<[_]>::to_vec)
+        .collect();
+    if let Some(depth) = depth {
+        components.drain(..components.len().saturating_sub(depth));
+    } else if config.parents == Parents::Short {
+        // like fish, all but the last directory are cut to their first char
+        let last = components.len().saturating_sub(1);
+        for component in &mut components[..last] {
+            let keep = if component[0].0 == '.' { 2 } else { 1 };
+            if component.len() > keep + 1 {
+                let end = component.len() - 1;
+                component.drain(keep..end);
+            }
+        }
+    }
+    components.concat()
+}
+
+/// Returns for each of `paths` how many parent directories tell it apart from
+/// the other paths with the same name.
+fn unique_depths(paths: &[&str]) -> Vec<usize> {
+    let components: Vec<Vec<&str>> = paths
+        .iter()
+        .map(|x| x.split('/').filter(|x| !x.is_empty()).rev().collect())
+        .collect();
+    components
+        .iter()
+        .map(|x| {
+            components
+                .iter()
+                .filter(|y| y != &x)
+                .map(|y| x.iter().zip(y).take_while(|(a, b)| a == b).count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Returns the parent depth of each listed path, `None` when parents are not
+/// limited to what tells them apart.
+fn depths(paths: &[&str], config: &Config) -> Vec<Option<usize>> {
+    if config.paths.parents == Parents::Unique {
+        unique_depths(paths).into_iter().map(Some).collect()
+    } else {
+        vec![None; paths.len()]
+    }
+}
+
+/// Splits `chars` into spans, styling the ones flagged as matched with
+/// `matched`. More than `max` chars are shortened in the middle with an
+/// ellipsis.
+fn highlight(
+    chars: Vec<(char, bool)>,
+    style: Style,
+    matched: Style,
+    max: usize,
+) -> Vec<Span<'static>> {
+    let chars = if chars.len() > max {
+        // keep more of the end, it tells entries apart better
+        let head = max.saturating_sub(1) / 3;
+        let tail = max.saturating_sub(1) - head;
+        let mut shortened = chars[..head].to_vec();
+        if max > 0 {
+            shortened.push(('…', false));
+        }
+        shortened.extend_from_slice(&chars[chars.len() - tail..]);
+        shortened
+    } else {
+        chars
+    };
+
+    let mut spans: Vec<Span> = Vec::new();
+    let mut current = String::new();
+    let mut current_matched = false;
+    for (c, is_matched) in chars {
+        if is_matched != current_matched && !current.is_empty() {
+            let style = if current_matched { matched } else { style };
+            spans.push(Span::styled(std::mem::take(&mut current), style));
+        }
+        current_matched = is_matched;
+        current.push(c);
+    }
+    if !current.is_empty() {
+        let style = if current_matched { matched } else { style };
+        spans.push(Span::styled(current, style));
+    }
+    spans
+}
+
+/// Formats a unix timestamp relative to now, e.g. "opened 3h ago".
+fn ago(timestamp: u64) -> String {
+    let secs = db::now().saturating_sub(timestamp);
+    let (n, unit) = match secs {
+        0..=59 => return "opened just now".to_owned(),
+        60..=3599 => (secs / 60, "m"),
+        3600..=86399 => (secs / 3600, "h"),
+        86400..=2591999 => (secs / 86400, "d"),
+        2592000..=31535999 => (secs / 2592000, "mo"),
+        _ => (secs / 31536000, "y"),
+    };
+    format!("opened {n}{unit} ago")
+}
+
+/// Where the parts of the start screen go on a terminal of a given size.
+struct Areas {
+    /// Area of the logo, `None` when even the one line title does not fit.
+    header: Option<Rect>,
+    /// Whether the full logo fits, otherwise a one line title is shown.
+    full_logo: bool,
+    list: Rect,
+    status: Option<Rect>,
+}
+
+impl Areas {
+    /// Splits `area` vertically, giving the list at least a few rows before
+    /// the logo is shrunk to a one line title, if there is one, and then hidden. Spare rows are used as
+    /// padding above the logo and the list, 5 rows at most each.
+    fn new(area: Rect, logo_width: u16, logo_height: u16, list_len: u16, title: bool) -> Self {
+        let (status, area) = if area.height >= 3 {
+            let (rest, status) = split_bottom(area, 1);
+            (Some(status), rest)
+        } else {
+            (None, area)
+        };
+        let min_list = list_len.min(8);
+        let full_logo = area.width >= logo_width && area.height >= logo_height + 1 + min_list;
+        let header_height = if full_logo {
+            logo_height
+        } else if title && area.height >= 2 + min_list {
+            1
+        } else {
+            0
+        };
+        if header_height == 0 {
+            return Self {
+                header: None,
+                full_logo: false,
+                list: area,
+                status,
+            };
+        }
+        let spare = area.height.saturating_sub(header_height + 1 + list_len);
+        let header_pad = (spare / 2).min(5);
+        let list_pad = (spare - header_pad).min(4);
+        let header = Rect {
+            y: area.y + header_pad,
+            height: header_height,
+            ..area
+        };
+        let list_top = header.bottom() + 1 + list_pad;
+        let list = Rect {
+            y: list_top,
+            height: area.bottom().saturating_sub(list_top),
+            ..area
+        };
+        Self {
+            header: Some(header),
+            full_logo,
+            list,
+            status,
+        }
+    }
+}
+
+fn split_bottom(area: Rect, height: u16) -> (Rect, Rect) {
+    let height = height.min(area.height);
+    let rest = Rect {
+        height: area.height - height,
+        ..area
+    };
+    let bottom = Rect {
+        y: rest.bottom(),
+        height,
+        ..area
+    };
+    (rest, bottom)
+}
+
+fn split_right(area: Rect, width: u16) -> (Rect, Rect) {
+    let width = width.min(area.width);
+    let rest = Rect {
+        width: area.width - width,
+        ..area
+    };
+    let right = Rect {
+        x: rest.right(),
+        width,
+        ..area
+    };
+    (rest, right)
+}
+
+/// Returns the left padding centering content of `width` in `area`.
+fn center(area: Rect, width: usize) -> u16 {
+    area.width
+        .saturating_sub(width.min(u16::MAX as usize) as u16)
+        / 2
+}
+
+/// Renders the preview of the selected entry in `area`, separated from the
+/// list by a line.
+fn render_preview(f: &mut Frame, state: &mut State, config: &Config, area: Rect) {
+    let title = state
+        .selected_entry(config)
+        .map(|x| display_dir(Path::new(&x.item.path), config))
+        .unwrap_or_default();
+    let block = Block::default()
+        .borders(Borders::LEFT)
+        .border_style(Style::default().fg(config.colors.path))
+        .title(Span::styled(
+            title,
+            Style::default().fg(config.colors.header),
+        ))
+        .padding(Padding::horizontal(1));
+    let lines = state.preview(config).map(<[_]>::to_vec).unwrap_or_default();
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+pub fn ui(f: &mut Frame, state: &mut State, config: &Config) {
+    state.poll_missing();
+    state.poll_branches();
+    let area = f.size();
+    f.render_widget(
+        Block::default().style(Style::default().bg(config.colors.background)),
+        area,
+    );
+    let area = if state.show_preview && area.width >= PREVIEW_MIN_WIDTH {
+        let (list, preview) = split_right(area, area.width / 2);
+        render_preview(f, state, config, preview);
+        list
+    } else {
+        area
+    };
+    // one column of margin on each side, and one for the scrollbar
+    let width = area.width.saturating_sub(3) as usize;
+
+    let header = Style::default().fg(config.colors.header);
+    let mut lines = Vec::new();
+    // line index of every entry, in entry order
+    let mut entry_lines = Vec::new();
+    if let Some(query) = &state.filter {
+        lines.push(Line::from(vec![
+            Span::styled("/", Style::default().fg(config.colors.key)),
+            Span::raw(query.clone()),
+            Span::styled("█", Style::default().fg(config.colors.bracket)),
+        ]));
+        lines.push(Line::default());
+        let filtered = state.filtered();
+        let paths: Vec<&str> = filtered.iter().map(|x| x.item.path.as_str()).collect();
+        let depths = depths(&paths, config);
+        if filtered.is_empty() {
+            lines.push(Line::styled(
+                "No matches",
+                Style::default().fg(config.colors.path),
+            ));
+        }
+        for (entry, depth) in filtered.iter().zip(depths) {
+            entry_lines.push(lines.len());
+            let note = state.note(entry.section, entry.item);
+            let mut line = entry
+                .item
+                .as_line(None, config, &entry.matches, depth, note, width);
+            state.mark_missing(&mut line, entry.item, config);
+            lines.push(line);
+        }
+    } else {
+        let sections = state.sections(config);
+        let paths: Vec<&str> = sections
+            .iter()
+            .flat_map(|x| &x.1)
+            .map(|x| x.path.as_str())
+            .collect();
+        let count = paths.len();
+        let mut depths = depths(&paths, config).into_iter();
+        let mut idx = 0;
+        for (i, (section, items)) in sections.iter().enumerate() {
+            if i > 0 {
+                lines.push(Line::default());
+            }
+            let title = match section {
+                Section::ProjectRecents => {
+                    let scope = state.scope.as_deref().unwrap_or(Path::new(""));
+                    format!("Recents in {}", display_dir(scope, config))
+                }
+                Section::Recents => "Recents".to_owned(),
+                Section::Projects => "Projects".to_owned(),
+                Section::Sessions => "Sessions".to_owned(),
+                Section::Bookmarks => "Bookmarks".to_owned(),
+            };
+            lines.push(Line::styled(title, header));
+            lines.push(Line::default());
+            for item in items {
+                entry_lines.push(lines.len());
+                let label = config.label(idx, count);
+                let depth = depths.next().flatten();
+                let note = state.note(*section, item);
+                let mut line = item.as_line(Some(label), config, &[], depth, note, width);
+                state.mark_missing(&mut line, item, config);
+                lines.push(line);
+                idx += 1;
+            }
+        }
+    }
+    if let Some(&line) = state.hovered.and_then(|x| entry_lines.get(x)) {
+        lines[line].patch_style(Style::default().bg(config.colors.hovered));
+    }
+    if let Some(&line) = entry_lines.get(state.selected) {
+        lines[line].patch_style(
+            Style::default()
+                .bg(config.colors.selected)
+                .add_modifier(Modifier::BOLD),
+        );
+    }
+
+    let logo_width = state.header.width();
+    let logo_height = state.header.height();
+    let logo_style = Style::default().fg(config.colors.logo);
+    // one line standing in for the header when it does not fit
+    let title = match (&state.header.title, state.header.extras.first()) {
+        (Some(title), _) => Some(Line::styled(
+            title.clone(),
+            logo_style.add_modifier(Modifier::BOLD),
+        )),
+        (None, Some(extra)) => Some(Line::styled(
+            extra.clone(),
+            Style::default().fg(config.colors.path),
+        )),
+        (None, None) => None,
+    };
+    let areas = Areas::new(
+        area,
+        logo_width.min(u16::MAX as usize) as u16,
+        logo_height.min(u16::MAX as usize) as u16,
+        lines.len().min(u16::MAX as usize) as u16,
+        title.is_some(),
+    );
+
+    match areas.header {
+        Some(header) if areas.full_logo => {
+            // logo lines are padded to the same width to be centered as a block
+            let logo = &state.header.logo;
+            let logo_width = logo.lines().map(|x| x.chars().count()).max();
+            let mut lines: Vec<_> = logo
+                .lines()
+                .map(|x| {
+                    let pad = logo_width.unwrap_or(0) - x.chars().count();
+                    Line::styled(format!("{x}{}", " ".repeat(pad)), logo_style)
+                })
+                .collect();
+            let extras = state.header.lines().skip(lines.len());
+            let extra_style = Style::default().fg(config.colors.path);
+            lines.extend(extras.map(|x| Line::styled(x.to_owned(), extra_style)));
+            f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), header);
+        }
+        Some(header) => {
+            let title = title.unwrap_or_default();
+            f.render_widget(Paragraph::new(title).alignment(Alignment::Center), header);
+        }
+        None => {}
+    }
+
+    let lines_width = lines.iter().map(|x| x.width()).max().unwrap_or(0);
+    let list = areas.list;
+    let inner = Rect {
+        x: list.x + center(list, lines_width),
+        width: list.width - center(list, lines_width),
+        ..list
+    };
+    let height = inner.height as usize;
+    state.page = (height / 2).max(1);
+    if let Some(&line) = entry_lines.get(state.selected) {
+        if state.selected == 0 {
+            state.scroll = 0;
+        } else if line < state.scroll {
+            state.scroll = line;
+        } else if line >= state.scroll + height {
+            state.scroll = line + 1 - height;
+        }
+    }
+    state.scroll = state.scroll.min(lines.len().saturating_sub(height));
+    let scroll = state.scroll;
+    state.rows = entry_lines
+        .iter()
+        .enumerate()
+        .filter(|&(_, &line)| (scroll..scroll + height).contains(&line))
+        .map(|(i, &line)| {
+            let rect = Rect::new(
+                inner.x,
+                inner.y + (line - scroll) as u16,
+                lines[line].width().min(u16::MAX as usize) as u16,
+                1,
+            );
+            (rect.intersection(inner), i)
+        })
+        .filter(|(rect, _)| !rect.is_empty())
+        .collect();
+
+    let overflow = lines.len() > height;
+    let total = lines.len();
+    f.render_widget(
+        Paragraph::new(lines).scroll((scroll.min(u16::MAX as usize) as u16, 0)),
+        inner,
+    );
+    if overflow && !list.is_empty() {
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None),
+            list,
+            &mut ScrollbarState::new(total.saturating_sub(height)).position(scroll),
+        );
+    }
+
+    let Some(status_area) = areas.status else {
+        return;
+    };
+    let status = match (&state.prompt, &state.message) {
+        (None, None) if !state.pending_label.is_empty() => Line::from(vec![
+            Span::styled("Jump: ", Style::default().fg(config.colors.header)),
+            Span::styled(
+                state.pending_label.clone(),
+                Style::default().fg(config.colors.key),
+            ),
+        ]),
+        (Some(Prompt::Delete(section, path)), _) => {
+            Line::from(format!("Delete {path} from {}? [y/n]", section.name()))
+        }
+        (Some(Prompt::Prune(count)), _) => {
+            Line::from(format!("Remove {}? [y/n]", missing_entries(*count)))
+        }
+        (Some(Prompt::Label(_, input)), _) => Line::from(vec![
+            Span::styled("Label: ", Style::default().fg(config.colors.header)),
+            Span::raw(input.clone()),
+            Span::styled("█", Style::default().fg(config.colors.bracket)),
+        ]),
+        (None, Some(message)) => {
+            Line::styled(message.clone(), Style::default().fg(config.colors.path))
+        }
+        (None, None) => return,
+    };
+    f.render_widget(
+        Paragraph::new(status).alignment(Alignment::Center),
+        status_area,
+    );
+}